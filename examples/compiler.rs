@@ -1,6 +1,6 @@
 extern crate alloc;
 
-use heaped::arena::{DroplessArena, TypedArena};
+use heaped::{arena::{DroplessArena, TypedArena}, arena_format};
 use alloc::{string::String, vec::Vec, format};
 
 #[derive(Debug)]
@@ -103,7 +103,7 @@ impl Compiler {
     ast_summaries.push(format!("Function {} with {} children", main_fn, fn_decl.children.len()));
     ast_summaries.push(format!("Block with {} statements", fn_body.children.len()));
     ast_summaries.push(format!("Return statement at span {:?}", return_stmt.span));
-    ast_summaries.push(format!("Literal at span {:?}", literal_node.span));
+    ast_summaries.push(format!("{} at span {:?}", literal_node.kind, literal_node.span));
     
     ast_summaries
   }
@@ -138,7 +138,7 @@ impl Compiler {
       assert_eq!(large_buffer.len(), 4096);
       
       for i in 0..100 {
-        let temp_string = temp_arena.alloc_str(&format!("temp_string_{}", i)).expect("should allocate temp string");
+        let temp_string = arena_format!(&temp_arena, "temp_string_{}", i);
         assert!(temp_string.contains(&i.to_string()));
       }
     }
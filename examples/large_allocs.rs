@@ -47,7 +47,6 @@ fn test_nested_scoped_arenas() {
   }
   
   fn stage2_processing(input: Vec<i32>) -> Vec<String> {
-    use alloc::string::ToString;
     let stage2_arena = TypedArena::<String>::new();
     let mut results = Vec::new();
     
@@ -8,11 +8,15 @@ use alloc::{
     AllocError,
     Allocator,
     Global,
+    Layout,
   },
   boxed::Box,
 };
 use core::{
-  mem::MaybeUninit,
+  mem::{
+    size_of,
+    MaybeUninit,
+  },
   ptr::NonNull,
 };
 
@@ -61,7 +65,7 @@ where
   }
 
   pub fn alloc_slice(&mut self, len: usize) -> Result<&mut [MaybeUninit<T>], AllocError> {
-    if self.entries + len <= self.capacity() {
+    if len <= self.capacity() - self.entries {
       unsafe {
         let ptr = self.storage.as_ptr().cast::<MaybeUninit<T>>().add(self.entries);
         self.entries += len;
@@ -73,6 +77,11 @@ where
   }
 
 
+  pub fn pop_slice(&mut self, len: usize) {
+    debug_assert!(len <= self.entries);
+    self.entries -= len;
+  }
+
   pub fn clear(&mut self) {
     self.entries = 0;
   }
@@ -81,6 +90,31 @@ where
     self.storage.as_ptr().cast::<MaybeUninit<T>>()
   }
 
+  pub fn contains(&self, ptr: *const u8) -> bool {
+    let start = self.storage.as_ptr().cast::<u8>() as usize;
+    let end = start + self.capacity() * size_of::<T>();
+    (start..end).contains(&(ptr as usize))
+  }
+
+  // Moves the storage if the allocator cannot shrink in place, so no
+  // references into the chunk may be alive.
+  pub fn shrink_to_fit(&mut self) -> Result<(), AllocError> {
+    let capacity = self.capacity();
+    if self.entries == 0 || self.entries == capacity || size_of::<T>() == 0 {
+      return Ok(());
+    }
+
+    let old_layout = Layout::array::<T>(capacity).map_err(|_| AllocError)?;
+    let new_layout = Layout::array::<T>(self.entries).map_err(|_| AllocError)?;
+    let ptr = unsafe {
+      self
+        .allocator
+        .shrink(self.storage.cast::<u8>(), old_layout, new_layout)?
+    };
+    self.storage = NonNull::slice_from_raw_parts(ptr.cast::<MaybeUninit<T>>(), self.entries);
+    Ok(())
+  }
+
 }
 
 
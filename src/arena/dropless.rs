@@ -3,11 +3,13 @@ use alloc::{
     AllocError,
     Allocator,
     Global,
+    Layout,
   },
   vec::Vec,
 };
 use core::{
   cell::RefCell,
+  ptr::NonNull,
   mem::{
     align_of,
     size_of,
@@ -16,17 +18,28 @@ use core::{
 
 use super::{
   chunk::ArenaChunk,
+  string::ArenaString,
   HUGE_PAGE,
   PAGE_SIZE,
 };
+use crate::{
+  observer::{
+    AllocObserver,
+    NoopObserver,
+  },
+  owns::Owns,
+};
 
 #[derive(Debug)]
-pub struct DroplessArena<A = Global>
+pub struct DroplessArena<A = Global, O = NoopObserver>
 where
   A: Allocator + Clone,
+  O: AllocObserver,
 {
   chunks: RefCell<Vec<ArenaChunk<u8, A>, A>>,
   allocator: A,
+  limit: Option<usize>,
+  observer: O,
 }
 
 impl<A> DroplessArena<A>
@@ -34,24 +47,74 @@ where
   A: Allocator + Clone,
 {
   pub fn new_in(allocator: A) -> Self {
+    Self::with_observer_in(allocator, NoopObserver)
+  }
+}
+
+impl<A, O> DroplessArena<A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  /// Create an arena that reports its activity to `observer`.
+  pub fn with_observer_in(allocator: A, observer: O) -> Self {
     Self {
       chunks: RefCell::new(Vec::new_in(allocator.clone())),
       allocator,
+      limit: None,
+      observer,
     }
   }
 
+  /// The observer receiving this arena's events.
+  pub fn observer(&self) -> &O {
+    &self.observer
+  }
+
+  /// Cap the total bytes reserved by chunks, or remove the cap with `None`.
+  ///
+  /// Once the cap would be exceeded, allocations fail with [`AllocError`].
+  pub fn set_limit(&mut self, limit: Option<usize>) {
+    self.limit = limit;
+  }
+
+  /// The configured byte limit, if any.
+  pub fn limit(&self) -> Option<usize> {
+    self.limit
+  }
+
+  /// Bytes that may still be reserved before hitting the limit, or `None`
+  /// when the arena is unlimited.
+  pub fn remaining_budget(&self) -> Option<usize> {
+    self
+      .limit
+      .map(|limit| limit.saturating_sub(self.allocated_bytes()))
+  }
+
   fn grow(&self, additional: usize) -> Result<(), AllocError> {
     let mut chunks = self.chunks.borrow_mut();
     
-    let new_cap = if chunks.is_empty() {
+    let mut new_cap = if chunks.is_empty() {
       PAGE_SIZE.max(additional)
     } else {
       let last_cap = chunks.last().unwrap().capacity();
       (last_cap * 2).min(HUGE_PAGE).max(additional)
     };
 
+    if let Some(limit) = self.limit {
+      let reserved: usize = chunks.iter().map(|chunk| chunk.capacity()).sum();
+      let remaining = limit.saturating_sub(reserved);
+      if additional > remaining {
+        return Err(AllocError);
+      }
+      new_cap = new_cap.min(remaining);
+    }
+
     let new_chunk = ArenaChunk::try_new_in(new_cap, self.allocator.clone())?;
     chunks.push(new_chunk);
+    drop(chunks);
+
+    self.observer.on_chunk_grow(new_cap);
     Ok(())
   }
 
@@ -88,13 +151,197 @@ where
     }
   }
 
+  /// Whether `slice` was allocated from this arena.
+  pub fn contains_slice<T>(&self, slice: &[T]) -> bool {
+    self.owns(NonNull::from(slice).cast())
+  }
+
+  /// Total number of bytes reserved by the arena's chunks.
+  pub fn allocated_bytes(&self) -> usize {
+    self.chunks.borrow().iter().map(|chunk| chunk.capacity()).sum()
+  }
+
+  /// Forget all allocations while keeping the chunks for reuse.
+  pub fn clear(&mut self) {
+    for chunk in self.chunks.get_mut().iter_mut() {
+      chunk.clear();
+    }
+    self.observer.on_reset();
+  }
+
+  /// Return empty chunks to the allocator and shrink the last chunk to its
+  /// used size.
+  pub fn shrink_to_fit(&mut self) {
+    let before = self.allocated_bytes();
+    let chunks = self.chunks.get_mut();
+    chunks.retain(|chunk| chunk.entries() > 0);
+    if let Some(last_chunk) = chunks.last_mut() {
+      let _ = last_chunk.shrink_to_fit();
+    }
+    chunks.shrink_to_fit();
+
+    let released = before - self.allocated_bytes();
+    if released > 0 {
+      self.observer.on_chunk_release(released);
+    }
+  }
+
+  /// Allocate a slice holding clones of every element in `slice`.
+  ///
+  /// The arena never runs destructors, so `T` must not need dropping. This
+  /// is checked at compile time:
+  ///
+  /// ```compile_fail
+  /// use heaped::arena::DroplessArena;
+  ///
+  /// let arena = DroplessArena::new();
+  /// arena.alloc_slice_clone(&[String::from("leaked")]);
+  /// ```
+  pub fn alloc_slice_clone<T>(&self, slice: &[T]) -> Result<&mut [T], AllocError>
+  where
+    T: Clone,
+  {
+    self.alloc_slice_fill_with(slice.len(), |i| slice[i].clone())
+  }
+
+  /// Allocate a slice of `len` copies of `value`.
+  pub fn alloc_slice_fill_copy<T>(&self, len: usize, value: T) -> Result<&mut [T], AllocError>
+  where
+    T: Copy,
+  {
+    self.alloc_slice_fill_with(len, |_| value)
+  }
+
+  /// Allocate a slice of `len` default values.
+  ///
+  /// `T` must not need dropping, as for
+  /// [`alloc_slice_clone`](Self::alloc_slice_clone).
+  pub fn alloc_slice_fill_default<T>(&self, len: usize) -> Result<&mut [T], AllocError>
+  where
+    T: Default,
+  {
+    self.alloc_slice_fill_with(len, |_| T::default())
+  }
+
+  /// Allocate a slice from an iterator with an exact length.
+  ///
+  /// Panics if the iterator yields fewer items than it reported. `T` must
+  /// not need dropping, as for [`alloc_slice_clone`](Self::alloc_slice_clone).
+  pub fn alloc_slice_fill_iter<T, I>(&self, iter: I) -> Result<&mut [T], AllocError>
+  where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: ExactSizeIterator,
+  {
+    let mut iter = iter.into_iter();
+    self.alloc_slice_fill_with(iter.len(), |_| {
+      iter.next().expect("ExactSizeIterator yielded fewer items than reported")
+    })
+  }
+
+  /// Allocate a slice of `len` elements where element `i` is `f(i)`.
+  ///
+  /// `T` must not need dropping, as for
+  /// [`alloc_slice_clone`](Self::alloc_slice_clone). If `f` panics, the
+  /// reserved bytes stay in the arena until it is dropped.
+  pub fn alloc_slice_fill_with<T, F>(&self, len: usize, mut f: F) -> Result<&mut [T], AllocError>
+  where
+    F: FnMut(usize) -> T,
+  {
+    const {
+      assert!(
+        !core::mem::needs_drop::<T>(),
+        "DroplessArena cannot hold values that need dropping"
+      )
+    };
+    if len == 0 {
+      return Ok(&mut []);
+    }
+
+    let layout = Layout::array::<T>(len).map_err(|_| AllocError)?;
+    let ptr = self.alloc_raw(layout.size(), layout.align())?.cast::<T>();
+    for i in 0..len {
+      unsafe { ptr.add(i).write(f(i)) };
+    }
+
+    unsafe { Ok(core::slice::from_raw_parts_mut(ptr, len)) }
+  }
+
   pub fn alloc_str(&self, s: &str) -> Result<&mut str, AllocError> {
     let bytes = self.alloc_slice(s.as_bytes())?;
     unsafe { Ok(core::str::from_utf8_unchecked_mut(bytes)) }
   }
 
-  fn alloc_raw(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+  /// Allocate the concatenation of `parts` as a single string.
+  pub fn alloc_concat(&self, parts: &[&str]) -> Result<&mut str, AllocError> {
+    let len = parts
+      .iter()
+      .try_fold(0usize, |len, part| len.checked_add(part.len()))
+      .ok_or(AllocError)?;
+    if len == 0 {
+      return Ok(Default::default());
+    }
+
+    let ptr = self.alloc_raw(len, 1)?;
+    let mut offset = 0;
+    for part in parts {
+      unsafe { core::ptr::copy_nonoverlapping(part.as_ptr(), ptr.add(offset), part.len()) };
+      offset += part.len();
+    }
+    unsafe {
+      let bytes = core::slice::from_raw_parts_mut(ptr, len);
+      Ok(core::str::from_utf8_unchecked_mut(bytes))
+    }
+  }
+
+  /// Start building a string at the tail of this arena.
+  pub fn string(&self) -> ArenaString<'_, A, O> {
+    ArenaString::new_in(self)
+  }
+
+  /// Extend the allocation ending at `ptr + old_size` to `new_size` bytes if
+  /// it is the most recent allocation and the chunk has room.
+  pub(crate) fn grow_tail(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> bool {
+    debug_assert!(new_size >= old_size);
+    let mut chunks = self.chunks.borrow_mut();
+    let Some(last_chunk) = chunks.last_mut() else {
+      return false;
+    };
+
+    let tail = unsafe { last_chunk.get_storage_ptr().add(last_chunk.entries()).cast::<u8>() };
+    if tail != ptr.wrapping_add(old_size) || last_chunk.alloc_slice(new_size - old_size).is_err() {
+      return false;
+    }
+    drop(chunks);
+
+    let additional = unsafe { Layout::from_size_align_unchecked(new_size - old_size, 1) };
+    self.observer.on_alloc(additional);
+    true
+  }
+
+  /// Give back the trailing bytes of the allocation ending at
+  /// `ptr + old_size` if it is the most recent allocation.
+  pub(crate) fn shrink_tail(&self, ptr: *mut u8, old_size: usize, new_size: usize) {
+    debug_assert!(new_size <= old_size);
+    let mut chunks = self.chunks.borrow_mut();
+    let Some(last_chunk) = chunks.last_mut() else {
+      return;
+    };
+
+    let tail = unsafe { last_chunk.get_storage_ptr().add(last_chunk.entries()).cast::<u8>() };
+    if tail != ptr.wrapping_add(old_size) {
+      return;
+    }
+    last_chunk.pop_slice(old_size - new_size);
+    drop(chunks);
+
+    let released = unsafe { Layout::from_size_align_unchecked(old_size - new_size, 1) };
+    self.observer.on_dealloc(released);
+  }
+
+  pub(crate) fn alloc_raw(&self, size: usize, align: usize) -> Result<*mut u8, AllocError> {
+    let layout = Layout::from_size_align(size, align).map_err(|_| AllocError)?;
     if size == 0 {
+      self.observer.on_alloc(layout);
       return Ok(align as *mut u8);
     }
 
@@ -106,26 +353,45 @@ where
       chunks = self.chunks.borrow_mut();
     }
 
-    loop {
+    let ptr = loop {
       if let Some(last_chunk) = chunks.last_mut() {
         let start = last_chunk.entries();
-        let aligned_start = (start + align - 1) & !(align - 1);
+        let base = last_chunk.get_storage_ptr() as usize;
+        let aligned_start = ((base + start + align - 1) & !(align - 1)) - base;
         let padding = aligned_start - start;
         
-        if aligned_start + size <= last_chunk.capacity() {
+        if aligned_start
+          .checked_add(size)
+          .is_some_and(|end| end <= last_chunk.capacity())
+        {
           for _ in 0..padding {
             let _ = last_chunk.alloc();
           }
           
           let result = last_chunk.alloc_slice(size)?;
-          return Ok(result.as_mut_ptr().cast::<u8>());
+          break result.as_mut_ptr().cast::<u8>();
         }
       }
       
       drop(chunks);
-      self.grow(size + align)?;
+      self.grow(size.checked_add(align).ok_or(AllocError)?)?;
       chunks = self.chunks.borrow_mut();
-    }
+    };
+    drop(chunks);
+
+    self.observer.on_alloc(layout);
+    Ok(ptr)
+  }
+}
+
+impl<A, O> Owns for DroplessArena<A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    let chunks = self.chunks.borrow();
+    chunks.iter().any(|chunk| chunk.contains(ptr.as_ptr()))
   }
 }
 
@@ -142,4 +408,17 @@ where
   fn default() -> Self {
     Self::new_in(A::default())
   }
-}
\ No newline at end of file
+}
+
+impl<A, O> Drop for DroplessArena<A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn drop(&mut self) {
+    let reserved = self.allocated_bytes();
+    if reserved > 0 {
+      self.observer.on_chunk_release(reserved);
+    }
+  }
+}
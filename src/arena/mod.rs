@@ -1,11 +1,13 @@
 mod chunk;
 pub mod dropless;
+pub mod string;
 pub mod typed;
 
 #[cfg(test)]
 pub mod tests;
 
 pub use dropless::DroplessArena;
+pub use string::ArenaString;
 pub use typed::TypedArena;
 
 const PAGE_SIZE: usize = 4096;
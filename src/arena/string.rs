@@ -0,0 +1,192 @@
+//! String builder that writes directly into a [`DroplessArena`].
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Global,
+};
+use core::{
+  fmt,
+  ptr::{
+    self,
+    NonNull,
+  },
+};
+
+use super::DroplessArena;
+use crate::observer::{
+  AllocObserver,
+  NoopObserver,
+};
+
+/// Smallest capacity reserved when the builder first needs memory.
+const MIN_CAPACITY: usize = 8;
+
+/// A growable string whose bytes live in a [`DroplessArena`].
+///
+/// While the builder owns the most recent allocation of the arena it grows
+/// in place at the chunk tail. Once something else is allocated in between,
+/// the contents are moved to a fresh, larger allocation.
+pub struct ArenaString<'a, A = Global, O = NoopObserver>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  /// Arena providing the storage.
+  arena: &'a DroplessArena<A, O>,
+  /// Start of the string bytes.
+  ptr: NonNull<u8>,
+  /// Number of initialized bytes.
+  len: usize,
+  /// Number of bytes reserved in the arena.
+  cap: usize,
+}
+
+impl<'a, A, O> ArenaString<'a, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  /// Create an empty builder without reserving any memory.
+  pub fn new_in(arena: &'a DroplessArena<A, O>) -> Self {
+    Self {
+      arena,
+      ptr: NonNull::dangling(),
+      len: 0,
+      cap: 0,
+    }
+  }
+
+  /// Try to create an empty builder with room for `capacity` bytes.
+  pub fn try_with_capacity_in(
+    capacity: usize,
+    arena: &'a DroplessArena<A, O>,
+  ) -> Result<Self, AllocError> {
+    let mut string = Self::new_in(arena);
+    string.try_reserve(capacity)?;
+    Ok(string)
+  }
+
+  /// Number of bytes written so far.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Whether nothing has been written yet.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Number of bytes that can be written without reserving more memory.
+  pub fn capacity(&self) -> usize {
+    self.cap
+  }
+
+  /// View the contents written so far.
+  pub fn as_str(&self) -> &str {
+    unsafe {
+      let bytes = core::slice::from_raw_parts(self.ptr.as_ptr(), self.len);
+      core::str::from_utf8_unchecked(bytes)
+    }
+  }
+
+  /// Try to reserve room for at least `additional` more bytes.
+  pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+    let required = self.len.checked_add(additional).ok_or(AllocError)?;
+    if required <= self.cap {
+      return Ok(());
+    }
+
+    if self.cap > 0 && self.arena.grow_tail(self.ptr.as_ptr(), self.cap, required) {
+      self.cap = required;
+      return Ok(());
+    }
+
+    let new_cap = required.max(self.cap.saturating_mul(2)).max(MIN_CAPACITY);
+    let new_ptr = self.arena.alloc_raw(new_cap, 1)?;
+    unsafe {
+      ptr::copy_nonoverlapping(self.ptr.as_ptr(), new_ptr, self.len);
+      self.ptr = NonNull::new_unchecked(new_ptr);
+    }
+    self.cap = new_cap;
+    Ok(())
+  }
+
+  /// Try to append a string slice.
+  pub fn try_push_str(&mut self, s: &str) -> Result<(), AllocError> {
+    self.try_reserve(s.len())?;
+    unsafe {
+      ptr::copy_nonoverlapping(s.as_ptr(), self.ptr.as_ptr().add(self.len), s.len());
+    }
+    self.len += s.len();
+    Ok(())
+  }
+
+  /// Append a string slice, panicking on allocation failure.
+  pub fn push_str(&mut self, s: &str) {
+    self.try_push_str(s).expect("Failed to grow ArenaString");
+  }
+
+  /// Append a single character, panicking on allocation failure.
+  pub fn push(&mut self, c: char) {
+    self.push_str(c.encode_utf8(&mut [0; 4]));
+  }
+
+  /// Finish building and return the string with the arena's lifetime.
+  ///
+  /// Unused reserved bytes are handed back to the arena when the string
+  /// is still its most recent allocation.
+  pub fn into_str(self) -> &'a str {
+    if self.cap > self.len {
+      self.arena.shrink_tail(self.ptr.as_ptr(), self.cap, self.len);
+    }
+    unsafe {
+      let bytes = core::slice::from_raw_parts(self.ptr.as_ptr(), self.len);
+      core::str::from_utf8_unchecked(bytes)
+    }
+  }
+}
+
+impl<A, O> fmt::Write for ArenaString<'_, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    self.try_push_str(s).map_err(|_| fmt::Error)
+  }
+}
+
+impl<A, O> fmt::Debug for ArenaString<'_, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl<A, O> fmt::Display for ArenaString<'_, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(self.as_str(), f)
+  }
+}
+
+/// Format a string directly into a [`DroplessArena`], returning `&str`
+/// borrowed from the arena.
+///
+/// Panics if the arena cannot allocate.
+#[macro_export]
+macro_rules! arena_format {
+  ($arena:expr, $($arg:tt)*) => {{
+    let mut string = $crate::arena::ArenaString::new_in($arena);
+    ::core::fmt::Write::write_fmt(&mut string, ::core::format_args!($($arg)*))
+      .expect("Failed to format into arena");
+    string.into_str()
+  }};
+}
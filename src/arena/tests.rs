@@ -1,5 +1,5 @@
 use alloc::alloc::Global;
-use crate::arena::{DroplessArena, TypedArena};
+use crate::{arena::{DroplessArena, TypedArena}, observer::testing::CountingObserver};
 
 #[test]
 fn test_dropless_arena_basic_allocation() {
@@ -95,10 +95,425 @@ fn test_alignment() {
   assert_eq!(long as *const u64 as usize % core::mem::align_of::<u64>(), 0);
 }
 
+#[test]
+fn test_over_aligned_allocation() {
+  #[repr(align(256))]
+  struct Page(u8);
+
+  let arena = DroplessArena::new();
+  arena.alloc(1u8).expect("should allocate u8");
+
+  for i in 0..4 {
+    let page = arena.alloc(Page(i)).expect("should allocate page");
+    assert_eq!(page.0, i);
+    assert_eq!(page as *const Page as usize % core::mem::align_of::<Page>(), 0);
+  }
+}
+
 #[test]
 fn test_custom_allocator() {
   let arena = DroplessArena::new_in(Global);
   
   let num = arena.alloc(42i32).expect("should allocate with custom allocator");
   assert_eq!(*num, 42);
-}
\ No newline at end of file
+}
+
+#[derive(Clone)]
+struct DropCounter<'a>(&'a core::cell::Cell<usize>);
+
+impl Drop for DropCounter<'_> {
+  fn drop(&mut self) {
+    self.0.set(self.0.get() + 1);
+  }
+}
+
+#[test]
+fn test_dropless_arena_slice_fill() {
+  let arena = DroplessArena::new();
+
+  let copies = arena.alloc_slice_fill_copy(4, 7u16).expect("should allocate copies");
+  assert_eq!(copies, &[7, 7, 7, 7]);
+
+  let defaults: &mut [u64] = arena.alloc_slice_fill_default(3).expect("should allocate defaults");
+  assert_eq!(defaults, &[0, 0, 0]);
+
+  let squares = arena.alloc_slice_fill_with(5, |i| i * i).expect("should allocate squares");
+  assert_eq!(squares, &[0, 1, 4, 9, 16]);
+
+  let doubled = arena
+    .alloc_slice_fill_iter([1, 2, 3].iter().map(|x| x * 2))
+    .expect("should allocate from iterator");
+  assert_eq!(doubled, &[2, 4, 6]);
+
+  let source = [Some("a"), None, Some("b")];
+  let cloned = arena.alloc_slice_clone(&source).expect("should allocate clones");
+  assert_eq!(cloned, &source);
+}
+
+#[test]
+fn test_dropless_arena_fill_reentrant() {
+  let arena = DroplessArena::new();
+
+  let strings = arena
+    .alloc_slice_fill_with(3, |i| &*arena.alloc_str(["x", "y", "z"][i]).unwrap())
+    .expect("should allow allocating inside the closure");
+  assert_eq!(strings, &["x", "y", "z"]);
+}
+
+#[test]
+fn test_dropless_arena_fill_panic() {
+  let arena = DroplessArena::new();
+
+  let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+    arena
+      .alloc_slice_fill_with(5, |i| {
+        if i == 3 {
+          panic!("fill failed");
+        }
+        i as u32
+      })
+      .map(|_| ())
+  }));
+  assert!(result.is_err());
+
+  let num = arena.alloc(1u32).expect("arena should stay usable");
+  assert_eq!(*num, 1);
+}
+
+#[test]
+fn test_typed_arena_slice_fill() {
+  let arena = TypedArena::<u32>::new();
+
+  let copies = arena.alloc_slice_fill_copy(3, 9).expect("should allocate copies");
+  assert_eq!(copies, &[9, 9, 9]);
+
+  let defaults = arena.alloc_slice_fill_default(2).expect("should allocate defaults");
+  assert_eq!(defaults, &[0, 0]);
+
+  let indices = arena.alloc_slice_fill_with(4, |i| i as u32).expect("should allocate");
+  assert_eq!(indices, &[0, 1, 2, 3]);
+
+  let collected = arena.alloc_slice_fill_iter(10..13).expect("should allocate from iterator");
+  assert_eq!(collected, &[10, 11, 12]);
+}
+
+#[test]
+fn test_typed_arena_slice_clone() {
+  use alloc::string::String;
+
+  let arena = TypedArena::<String>::new();
+
+  let source = [String::from("hello"), String::from("world")];
+  let cloned = arena.alloc_slice_clone(&source).expect("should allocate clones");
+  assert_eq!(cloned, &source);
+}
+
+#[test]
+fn test_typed_arena_fill_panic() {
+  let drops = core::cell::Cell::new(0);
+
+  {
+    let arena = TypedArena::<DropCounter>::new();
+    let _first = arena.alloc(DropCounter(&drops)).expect("should allocate");
+
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+      arena
+        .alloc_slice_fill_with(5, |i| {
+          if i == 2 {
+            panic!("fill failed");
+          }
+          DropCounter(&drops)
+        })
+        .map(|_| ())
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 2);
+
+    let _second = arena.alloc(DropCounter(&drops)).expect("arena should stay usable");
+  }
+
+  assert_eq!(drops.get(), 4);
+}
+
+#[test]
+fn test_typed_arena_fill_reentrant() {
+  let arena = TypedArena::<u32>::new();
+  let first = arena.alloc(7).expect("should allocate");
+
+  let slice = arena
+    .alloc_slice_fill_with(4, |i| {
+      let nested = arena.alloc(100 + i as u32).expect("should allocate inside the closure");
+      assert!(arena.contains(first) && arena.contains(nested));
+      assert!(arena.allocated_bytes() > 0 && arena.remaining_budget().is_none());
+      *nested + 1
+    })
+    .expect("should allow allocating inside the closure");
+  assert_eq!(slice, &[101, 102, 103, 104]);
+
+  let after = arena.alloc(8).expect("should allocate after the fill");
+  assert_eq!((*first, *after), (7, 8));
+  assert!(arena.contains(&slice[3]));
+}
+
+#[test]
+fn test_typed_arena_fill_reentrant_panic() {
+  let drops = core::cell::Cell::new(0);
+
+  {
+    let arena = TypedArena::<DropCounter>::new();
+
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+      arena
+        .alloc_slice_fill_with(4, |i| {
+          arena.alloc(DropCounter(&drops)).expect("should allocate inside the closure");
+          if i == 2 {
+            panic!("fill failed");
+          }
+          DropCounter(&drops)
+        })
+        .map(|_| ())
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 2);
+
+    let _slice = arena
+      .alloc_slice_fill_with(2, |_| DropCounter(&drops))
+      .expect("arena should stay usable");
+  }
+
+  // Two written slice elements, three nested values and the second slice.
+  assert_eq!(drops.get(), 7);
+}
+
+#[test]
+fn test_arena_format() {
+  let arena = DroplessArena::new();
+
+  let formatted = crate::arena_format!(&arena, "temp_string_{}", 42);
+  assert_eq!(formatted, "temp_string_42");
+
+  let empty = crate::arena_format!(&arena, "");
+  assert!(empty.is_empty());
+}
+
+#[test]
+fn test_arena_string_grows_in_place() {
+  use core::fmt::Write;
+
+  let arena = DroplessArena::new();
+
+  let mut string = arena.string();
+  string.push_str("hello");
+  let start = string.as_str().as_ptr();
+  for i in 0..100 {
+    write!(string, " {}", i).expect("should write");
+  }
+  assert_eq!(string.as_str().as_ptr(), start);
+  assert!(string.as_str().starts_with("hello 0 1 2"));
+  assert!(string.as_str().ends_with(" 99"));
+
+  let finished = string.into_str();
+  let next = arena.alloc(1u8).expect("should allocate");
+  assert_eq!(finished.as_ptr().wrapping_add(finished.len()), next as *const u8);
+}
+
+#[test]
+fn test_arena_string_relocates_after_interleaved_alloc() {
+  let arena = DroplessArena::new();
+
+  let mut string = arena.string();
+  string.push_str("abc");
+  let other = arena.alloc_str("interleaved").expect("should allocate");
+  for _ in 0..10 {
+    string.push('d');
+  }
+
+  assert_eq!(other, "interleaved");
+  assert_eq!(string.into_str(), "abcdddddddddd");
+}
+
+#[test]
+fn test_alloc_concat() {
+  let arena = DroplessArena::new();
+
+  let joined = arena.alloc_concat(&["foo", "", "bar", "baz"]).expect("should concat");
+  assert_eq!(joined, "foobarbaz");
+
+  let empty = arena.alloc_concat(&[]).expect("should handle no parts");
+  assert!(empty.is_empty());
+}
+
+#[test]
+fn test_dropless_arena_shrink_to_fit() {
+  let mut arena = DroplessArena::new();
+
+  arena.alloc_slice_fill_copy(10_000, 0u8).expect("should allocate");
+  assert!(arena.allocated_bytes() >= 10_000);
+
+  arena.clear();
+  arena.shrink_to_fit();
+  assert_eq!(arena.allocated_bytes(), 0);
+
+  arena.alloc_slice_fill_copy(10, 1u8).expect("should allocate after shrinking");
+  arena.shrink_to_fit();
+  assert_eq!(arena.allocated_bytes(), 10);
+
+  let num = arena.alloc(7u64).expect("should grow again");
+  assert_eq!(*num, 7);
+}
+
+#[test]
+fn test_typed_arena_shrink_to_fit() {
+  let mut arena = TypedArena::<u64>::new();
+
+  arena.alloc_slice_fill_copy(5_000, 3).expect("should allocate");
+  arena.clear();
+  arena.shrink_to_fit();
+  assert_eq!(arena.allocated_bytes(), 0);
+
+  arena.alloc_slice_fill_copy(3, 4).expect("should allocate");
+  arena.shrink_to_fit();
+  assert_eq!(arena.allocated_bytes(), 3 * core::mem::size_of::<u64>());
+
+  let value = arena.alloc(5).expect("should grow again");
+  assert_eq!(*value, 5);
+}
+
+#[test]
+fn test_dropless_arena_limit() {
+  let mut arena = DroplessArena::new();
+  arena.set_limit(Some(10_000));
+  assert_eq!(arena.remaining_budget(), Some(10_000));
+
+  let mut allocated = 0;
+  while arena.alloc_slice_fill_copy(100, 0u8).is_ok() {
+    allocated += 100;
+  }
+  assert!(allocated > 0);
+  assert!(arena.allocated_bytes() <= 10_000);
+  assert_eq!(
+    arena.remaining_budget(),
+    Some(10_000 - arena.allocated_bytes())
+  );
+
+  assert!(arena.alloc_slice_fill_copy(usize::MAX, 0u8).is_err());
+  assert!(arena.alloc_slice_fill_copy(usize::MAX / 8, 0u64).is_err());
+  assert!(arena.alloc_concat(&["a"; 20_000]).is_err());
+  assert!(arena.string().try_reserve(usize::MAX).is_err());
+
+  arena.set_limit(None);
+  assert_eq!(arena.remaining_budget(), None);
+  arena.alloc_slice_fill_copy(100_000, 0u8).expect("should allocate without a limit");
+}
+
+#[test]
+fn test_typed_arena_limit() {
+  let mut arena = TypedArena::<u64>::new();
+  arena.set_limit(Some(1024));
+
+  let mut count = 0;
+  while arena.alloc(count).is_ok() {
+    count += 1;
+  }
+  assert_eq!(count, 128);
+  assert_eq!(arena.remaining_budget(), Some(0));
+  assert!(arena.alloc_slice_fill_copy(usize::MAX, 0).is_err());
+
+  let mut zst = TypedArena::<()>::new();
+  zst.set_limit(Some(0));
+  zst.alloc(()).expect("zero-sized values need no budget");
+}
+
+#[test]
+fn test_dropless_arena_observer() {
+  let observer = CountingObserver::default();
+
+  {
+    let mut arena = DroplessArena::with_observer_in(Global, &observer);
+    arena.alloc(1u32).expect("should allocate");
+    arena.alloc_str("hello").expect("should allocate string");
+    assert_eq!(observer.allocs.get(), 2);
+    assert_eq!(observer.alloc_bytes.get(), 9);
+    assert_eq!(observer.grown.get(), arena.allocated_bytes());
+
+    let mut string = arena.string();
+    string.push_str("abc");
+    arena.alloc(0u8).expect("should allocate");
+    string.push_str("defghijkl");
+    assert_eq!(string.into_str(), "abcdefghijkl");
+    assert_eq!(observer.deallocs.get(), 1);
+
+    arena.clear();
+    assert_eq!(observer.resets.get(), 1);
+    arena.shrink_to_fit();
+    assert_eq!(observer.released.get(), observer.grown.get());
+
+    arena.alloc(2u64).expect("should allocate");
+  }
+
+  assert_eq!(observer.released.get(), observer.grown.get());
+}
+
+#[test]
+fn test_typed_arena_observer() {
+  let observer = CountingObserver::default();
+
+  {
+    let mut arena = TypedArena::<u32, _, _>::with_observer_in(Global, &observer);
+    arena.alloc(1).expect("should allocate");
+    arena.alloc_slice(&[1, 2, 3]).expect("should allocate slice");
+    assert_eq!(observer.allocs.get(), 2);
+    assert_eq!(observer.alloc_bytes.get(), 16);
+    assert_eq!(observer.grown.get(), arena.allocated_bytes());
+
+    arena.clear();
+    assert_eq!(observer.resets.get(), 1);
+  }
+
+  assert_eq!(observer.released.get(), observer.grown.get());
+}
+
+#[test]
+fn test_arena_owns() {
+  use crate::owns::Owns;
+  use core::ptr::NonNull;
+
+  let dropless = DroplessArena::new();
+  let typed = TypedArena::<u64>::new();
+  let outside = 0u64;
+
+  let slice = dropless.alloc_slice(&[1u16, 2, 3]).expect("should allocate slice");
+  let value = typed.alloc(5).expect("should allocate");
+  assert!(dropless.contains_slice(slice));
+  assert!(typed.contains(value));
+  assert!(!dropless.contains_slice(core::slice::from_ref(&outside)));
+  assert!(!typed.contains(&outside));
+  assert!(!dropless.owns(NonNull::from(&*value).cast()));
+  assert!(!typed.owns(NonNull::from(&slice[0]).cast()));
+}
+
+#[test]
+fn test_arena_owns_inside_fill() {
+  use crate::owns::Owns;
+  use core::ptr::NonNull;
+
+  let dropless = DroplessArena::new();
+  let typed = TypedArena::<u64>::new();
+  let first = typed.alloc(1).expect("should allocate");
+  let byte = dropless.alloc(2u8).expect("should allocate");
+
+  typed
+    .alloc_slice_fill_with(3, |i| {
+      assert!(typed.owns(NonNull::from(&*first).cast()));
+      assert!(dropless.owns(NonNull::from(&*byte).cast()));
+      i as u64
+    })
+    .expect("should allocate slice");
+  dropless
+    .alloc_slice_fill_with(3, |i| {
+      assert!(dropless.owns(NonNull::from(&*byte).cast()));
+      assert!(typed.owns(NonNull::from(&*first).cast()));
+      i as u64
+    })
+    .expect("should allocate slice");
+}
@@ -3,12 +3,17 @@ use alloc::{
     AllocError,
     Allocator,
     Global,
+    Layout,
   },
   vec::Vec,
 };
 use core::{
-  cell::RefCell,
+  cell::{
+    Cell,
+    RefCell,
+  },
   ptr,
+  ptr::NonNull,
 };
 
 use super::{
@@ -16,14 +21,27 @@ use super::{
   HUGE_PAGE,
   PAGE_SIZE,
 };
+use crate::{
+  observer::{
+    AllocObserver,
+    NoopObserver,
+  },
+  owns::Owns,
+};
 
 #[derive(Debug)]
-pub struct TypedArena<T, A = Global>
+pub struct TypedArena<T, A = Global, O = NoopObserver>
 where
   A: Allocator + Clone,
+  O: AllocObserver,
 {
   chunks: RefCell<Vec<ArenaChunk<T, A>, A>>,
+  /// Number of leading chunks closed to new allocations while a slice fill
+  /// is writing into the last of them.
+  sealed: Cell<usize>,
   allocator: A,
+  limit: Option<usize>,
+  observer: O,
 }
 
 impl<T, A> TypedArena<T, A>
@@ -31,16 +49,55 @@ where
   A: Allocator + Clone,
 {
   pub fn new_in(allocator: A) -> Self {
+    Self::with_observer_in(allocator, NoopObserver)
+  }
+}
+
+impl<T, A, O> TypedArena<T, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  /// Create an arena that reports its activity to `observer`.
+  pub fn with_observer_in(allocator: A, observer: O) -> Self {
     Self {
       chunks: RefCell::new(Vec::new_in(allocator.clone())),
+      sealed: Cell::new(0),
       allocator,
+      limit: None,
+      observer,
     }
   }
 
+  /// The observer receiving this arena's events.
+  pub fn observer(&self) -> &O {
+    &self.observer
+  }
+
+  /// Cap the total bytes reserved by chunks, or remove the cap with `None`.
+  ///
+  /// Once the cap would be exceeded, allocations fail with [`AllocError`].
+  pub fn set_limit(&mut self, limit: Option<usize>) {
+    self.limit = limit;
+  }
+
+  /// The configured byte limit, if any.
+  pub fn limit(&self) -> Option<usize> {
+    self.limit
+  }
+
+  /// Bytes that may still be reserved before hitting the limit, or `None`
+  /// when the arena is unlimited.
+  pub fn remaining_budget(&self) -> Option<usize> {
+    self
+      .limit
+      .map(|limit| limit.saturating_sub(self.allocated_bytes()))
+  }
+
   fn grow(&self, additional: usize) -> Result<(), AllocError> {
     let mut chunks = self.chunks.borrow_mut();
     
-    let new_cap = if chunks.is_empty() {
+    let mut new_cap = if chunks.is_empty() {
       let default_cap = PAGE_SIZE / core::mem::size_of::<T>().max(1);
       default_cap.max(additional).max(1)
     } else {
@@ -49,8 +106,23 @@ where
       (last_cap * 2).min(max_cap).max(additional)
     };
 
+    if let Some(limit) = self.limit {
+      let elem_size = core::mem::size_of::<T>();
+      let reserved = chunks.iter().map(|chunk| chunk.capacity()).sum::<usize>() * elem_size;
+      // Zero-sized types never consume budget.
+      if let Some(max_cap) = limit.saturating_sub(reserved).checked_div(elem_size) {
+        if additional > max_cap {
+          return Err(AllocError);
+        }
+        new_cap = new_cap.min(max_cap);
+      }
+    }
+
     let new_chunk = ArenaChunk::try_new_in(new_cap, self.allocator.clone())?;
     chunks.push(new_chunk);
+    drop(chunks);
+
+    self.observer.on_chunk_grow(new_cap * core::mem::size_of::<T>());
     Ok(())
   }
 
@@ -63,19 +135,23 @@ where
       chunks = self.chunks.borrow_mut();
     }
 
-    loop {
-      if let Some(last_chunk) = chunks.last_mut()
+    let ptr = loop {
+      if chunks.len() > self.sealed.get()
+        && let Some(last_chunk) = chunks.last_mut()
         && let Ok(slot) = last_chunk.alloc() {
-          unsafe {
-            let ptr = slot.as_mut_ptr();
-            ptr::write(ptr, value);
-            return Ok(&mut *ptr);
-          }
+          break slot.as_mut_ptr();
         }
       
       drop(chunks);
       self.grow(1)?;
       chunks = self.chunks.borrow_mut();
+    };
+    drop(chunks);
+
+    self.observer.on_alloc(Layout::new::<T>());
+    unsafe {
+      ptr::write(ptr, value);
+      Ok(&mut *ptr)
     }
   }
 
@@ -83,13 +159,88 @@ where
   where
     T: Copy,
   {
-    if slice.is_empty() {
+    self.alloc_slice_fill_with(slice.len(), |i| slice[i])
+  }
+
+  /// Whether `value` was allocated from this arena.
+  pub fn contains(&self, value: &T) -> bool {
+    self.owns(NonNull::from(value).cast())
+  }
+
+  /// Total number of bytes reserved by the arena's chunks.
+  pub fn allocated_bytes(&self) -> usize {
+    let chunks = self.chunks.borrow();
+    chunks.iter().map(|chunk| chunk.capacity()).sum::<usize>() * core::mem::size_of::<T>()
+  }
+
+  /// Allocate a slice holding clones of every element in `slice`.
+  pub fn alloc_slice_clone(&self, slice: &[T]) -> Result<&mut [T], AllocError>
+  where
+    T: Clone,
+  {
+    self.alloc_slice_fill_with(slice.len(), |i| slice[i].clone())
+  }
+
+  /// Allocate a slice of `len` copies of `value`.
+  pub fn alloc_slice_fill_copy(&self, len: usize, value: T) -> Result<&mut [T], AllocError>
+  where
+    T: Copy,
+  {
+    self.alloc_slice_fill_with(len, |_| value)
+  }
+
+  /// Allocate a slice of `len` default values.
+  pub fn alloc_slice_fill_default(&self, len: usize) -> Result<&mut [T], AllocError>
+  where
+    T: Default,
+  {
+    self.alloc_slice_fill_with(len, |_| T::default())
+  }
+
+  /// Allocate a slice from an iterator with an exact length.
+  ///
+  /// Panics if the iterator yields fewer items than it reported.
+  pub fn alloc_slice_fill_iter<I>(&self, iter: I) -> Result<&mut [T], AllocError>
+  where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: ExactSizeIterator,
+  {
+    let mut iter = iter.into_iter();
+    self.alloc_slice_fill_with(iter.len(), |_| {
+      iter.next().expect("ExactSizeIterator yielded fewer items than reported")
+    })
+  }
+
+  /// Allocate a slice of `len` elements where element `i` is `f(i)`.
+  ///
+  /// `f` may allocate from this arena; those allocations are placed in a
+  /// later chunk. If `f` panics, the elements written so far are dropped
+  /// and the reserved slots are released.
+  pub fn alloc_slice_fill_with<F>(&self, len: usize, mut f: F) -> Result<&mut [T], AllocError>
+  where
+    F: FnMut(usize) -> T,
+  {
+    if len == 0 {
       return Ok(&mut []);
     }
 
-    let len = slice.len();
+    let layout = Layout::array::<T>(len).map_err(|_| AllocError)?;
+    let mut guard = self.reserve_slice(len)?;
+    let ptr = guard.ptr;
+
+    while guard.initialized < len {
+      unsafe { ptr.add(guard.initialized).write(f(guard.initialized)) };
+      guard.initialized += 1;
+    }
+
+    drop(guard);
+    self.observer.on_alloc(layout);
+    unsafe { Ok(core::slice::from_raw_parts_mut(ptr, len)) }
+  }
+
+  fn reserve_slice(&self, len: usize) -> Result<FillGuard<'_, T, A, O>, AllocError> {
     let mut chunks = self.chunks.borrow_mut();
-    
+
     if chunks.is_empty() {
       drop(chunks);
       self.grow(len)?;
@@ -97,18 +248,21 @@ where
     }
 
     loop {
-      if let Some(last_chunk) = chunks.last_mut()
+      if chunks.len() > self.sealed.get()
+        && let Some(last_chunk) = chunks.last_mut()
         && let Ok(slots) = last_chunk.alloc_slice(len) {
-          unsafe {
-            for (i, &item) in slice.iter().enumerate() {
-              let ptr = slots[i].as_mut_ptr();
-              ptr::write(ptr, item);
-            }
-            let ptr = slots[0].as_mut_ptr();
-            return Ok(core::slice::from_raw_parts_mut(ptr, len));
-          }
+          let ptr = slots.as_mut_ptr().cast::<T>();
+          let chunk = chunks.len() - 1;
+          return Ok(FillGuard {
+            arena: self,
+            chunk,
+            unsealed: self.sealed.replace(chunk + 1),
+            ptr,
+            len,
+            initialized: 0,
+          });
         }
-      
+
       drop(chunks);
       self.grow(len)?;
       chunks = self.chunks.borrow_mut();
@@ -125,6 +279,24 @@ where
       }
       chunk.clear();
     }
+    self.observer.on_reset();
+  }
+
+  /// Return empty chunks to the allocator and shrink the last chunk to its
+  /// used size.
+  pub fn shrink_to_fit(&mut self) {
+    let before = self.allocated_bytes();
+    let chunks = self.chunks.get_mut();
+    chunks.retain(|chunk| chunk.entries() > 0);
+    if let Some(last_chunk) = chunks.last_mut() {
+      let _ = last_chunk.shrink_to_fit();
+    }
+    chunks.shrink_to_fit();
+
+    let released = before - self.allocated_bytes();
+    if released > 0 {
+      self.observer.on_chunk_release(released);
+    }
   }
 }
 
@@ -132,11 +304,12 @@ where
 // 1. We only call drop_in_place() on each T, running T's own destructor
 // 2. We never access or dereference any potential references that T might contain
 // 3. After dropping each T, we only deallocate memory - no further access to T
-// 4. The allocator A is not dangled, only T is allowed to dangle
+// 4. The allocator A and observer O are not dangled, only T is allowed to dangle
 // 5. This mirrors the same safety pattern used in rustc_arena::TypedArena
-unsafe impl<#[may_dangle] T, A> Drop for TypedArena<T, A>
+unsafe impl<#[may_dangle] T, A, O> Drop for TypedArena<T, A, O>
 where
   A: Allocator + Clone,
+  O: AllocObserver,
 {
   fn drop(&mut self) {
     for chunk in self.chunks.borrow_mut().iter_mut() {
@@ -147,6 +320,22 @@ where
         }
       }
     }
+
+    let reserved = self.allocated_bytes();
+    if reserved > 0 {
+      self.observer.on_chunk_release(reserved);
+    }
+  }
+}
+
+impl<T, A, O> Owns for TypedArena<T, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    let chunks = self.chunks.borrow();
+    chunks.iter().any(|chunk| chunk.contains(ptr.as_ptr()))
   }
 }
 
@@ -164,3 +353,39 @@ where
     Self::new_in(A::default())
   }
 }
+
+
+/// Keeps a partially initialized slice consistent if filling it panics.
+///
+/// The chunk holding the slice stays sealed while the guard lives, so the
+/// slice remains at its tail even if the fill allocates from the arena.
+struct FillGuard<'a, T, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  arena: &'a TypedArena<T, A, O>,
+  chunk: usize,
+  unsealed: usize,
+  ptr: *mut T,
+  len: usize,
+  initialized: usize,
+}
+
+impl<T, A, O> Drop for FillGuard<'_, T, A, O>
+where
+  A: Allocator + Clone,
+  O: AllocObserver,
+{
+  fn drop(&mut self) {
+    self.arena.sealed.set(self.unsealed);
+    if self.initialized == self.len {
+      return;
+    }
+
+    unsafe {
+      ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr, self.initialized));
+    }
+    self.arena.chunks.borrow_mut()[self.chunk].pop_slice(self.len);
+  }
+}
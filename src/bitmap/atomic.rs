@@ -0,0 +1,239 @@
+//! Bitmap with atomic words for lock-free slot claiming.
+
+use alloc::alloc::{
+  Allocator,
+  Global,
+  Layout,
+};
+use core::{
+  ptr::NonNull,
+  sync::atomic::{
+    AtomicUsize,
+    Ordering,
+  },
+};
+
+use super::{
+  BitmapError,
+  WORD_BITS,
+};
+
+/// A fixed-size bitmap whose bits can be claimed and released through `&self`.
+///
+/// A set bit marks a claimed slot. Successful claims synchronize with the
+/// release of the same bit, so data guarded by a slot can be handed between
+/// threads.
+#[derive(Debug)]
+pub struct AtomicBitmap<A = Global>
+where
+  A: Allocator,
+{
+  /// Allocator used for backing storage.
+  allocator: A,
+  /// First word of the storage.
+  words: NonNull<AtomicUsize>,
+  /// Number of words in the storage.
+  fields: usize,
+  /// Number of bits in the bitmap.
+  len: usize,
+}
+
+unsafe impl<A> Send for AtomicBitmap<A> where A: Allocator + Send {}
+unsafe impl<A> Sync for AtomicBitmap<A> where A: Allocator + Sync {}
+
+impl<A> AtomicBitmap<A>
+where
+  A: Allocator,
+{
+  /// Try to create a bitmap of `len` clear bits in the given allocator.
+  pub fn try_new_in(allocator: A, len: usize) -> Result<Self, BitmapError> {
+    let fields = len.div_ceil(WORD_BITS);
+    let layout = Layout::array::<AtomicUsize>(fields).map_err(|_| BitmapError::InvalidSize)?;
+    let ptr = allocator
+      .allocate_zeroed(layout)
+      .map_err(|_| BitmapError::AllocError)?;
+    Ok(Self {
+      allocator,
+      words: ptr.cast(),
+      fields,
+      len,
+    })
+  }
+
+  /// Create a bitmap, panicking on failure.
+  pub fn new_in(allocator: A, len: usize) -> Self {
+    Self::try_new_in(allocator, len).expect("Failed to create AtomicBitmap")
+  }
+}
+
+impl AtomicBitmap<Global> {
+  /// Create a bitmap using the global allocator.
+  pub fn new(len: usize) -> Self {
+    Self::new_in(Global, len)
+  }
+}
+
+impl<A> AtomicBitmap<A>
+where
+  A: Allocator,
+{
+  fn words(&self) -> &[AtomicUsize] {
+    // SAFETY: `words` points to `fields` zero-initialized atomics owned by `self`.
+    unsafe { core::slice::from_raw_parts(self.words.as_ptr(), self.fields) }
+  }
+
+  /// Word and bit mask for `index`, panicking on out-of-bounds.
+  fn locate(&self, index: usize) -> (&AtomicUsize, usize) {
+    assert!(index < self.len, "AtomicBitmap index out of bounds");
+    (&self.words()[index / WORD_BITS], 1 << (index % WORD_BITS))
+  }
+
+  /// Number of bits in the bitmap.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Whether the bitmap holds no bits.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Whether the bit at `index` is currently claimed.
+  pub fn is_claimed(&self, index: usize) -> bool {
+    let (word, mask) = self.locate(index);
+    word.load(Ordering::Acquire) & mask != 0
+  }
+
+  /// Set the bit at `index` if it is clear, returning whether this call set it.
+  pub fn try_claim(&self, index: usize) -> bool {
+    let (word, mask) = self.locate(index);
+    word.fetch_or(mask, Ordering::AcqRel) & mask == 0
+  }
+
+  /// Clear the bit at `index`, returning whether it was claimed.
+  pub fn release(&self, index: usize) -> bool {
+    let (word, mask) = self.locate(index);
+    word.fetch_and(!mask, Ordering::AcqRel) & mask != 0
+  }
+
+  /// Atomically find a clear bit, set it, and return its index.
+  pub fn claim_first_clear(&self) -> Option<usize> {
+    for (index, word) in self.words().iter().enumerate() {
+      let valid = if index == self.fields - 1 && !self.len.is_multiple_of(WORD_BITS) {
+        (1 << (self.len % WORD_BITS)) - 1
+      } else {
+        usize::MAX
+      };
+      let mut current = word.load(Ordering::Relaxed);
+      loop {
+        let free = !current & valid;
+        if free == 0 {
+          break;
+        }
+        let mask = free & free.wrapping_neg();
+        match word.compare_exchange_weak(
+          current,
+          current | mask,
+          Ordering::AcqRel,
+          Ordering::Relaxed,
+        ) {
+          Ok(_) => return Some(index * WORD_BITS + mask.trailing_zeros() as usize),
+          Err(actual) => current = actual,
+        }
+      }
+    }
+    None
+  }
+
+  /// Number of claimed bits at the time of the call.
+  pub fn count_claimed(&self) -> usize {
+    self
+      .words()
+      .iter()
+      .map(|word| word.load(Ordering::Relaxed).count_ones() as usize)
+      .sum()
+  }
+}
+
+impl<A> Drop for AtomicBitmap<A>
+where
+  A: Allocator,
+{
+  fn drop(&mut self) {
+    let layout = Layout::array::<AtomicUsize>(self.fields).unwrap();
+    unsafe {
+      self.allocator.deallocate(self.words.cast(), layout);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::vec::Vec;
+  use std::thread;
+
+  #[test]
+  fn test_claim_release() {
+    let bitmap = AtomicBitmap::new(70);
+    assert!(bitmap.try_claim(65));
+    assert!(!bitmap.try_claim(65));
+    assert!(bitmap.is_claimed(65));
+    assert!(bitmap.release(65));
+    assert!(!bitmap.release(65));
+
+    let claimed: Vec<usize> = core::iter::from_fn(|| bitmap.claim_first_clear()).collect();
+    assert_eq!(claimed, (0..70).collect::<Vec<_>>());
+    assert_eq!(bitmap.count_claimed(), 70);
+
+    bitmap.release(42);
+    assert_eq!(bitmap.claim_first_clear(), Some(42));
+    assert!(AtomicBitmap::new(0).claim_first_clear().is_none());
+  }
+
+  #[test]
+  fn test_concurrent_claim_first_clear() {
+    const THREADS: usize = 8;
+    const SLOTS: usize = 4000;
+
+    let bitmap = AtomicBitmap::new(SLOTS);
+    let claimed: Vec<Vec<usize>> = thread::scope(|scope| {
+      let workers: Vec<_> = (0..THREADS)
+        .map(|_| scope.spawn(|| core::iter::from_fn(|| bitmap.claim_first_clear()).collect()))
+        .collect();
+      workers.into_iter().map(|w| w.join().unwrap()).collect()
+    });
+
+    let mut all: Vec<usize> = claimed.into_iter().flatten().collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..SLOTS).collect::<Vec<_>>());
+    assert_eq!(bitmap.count_claimed(), SLOTS);
+  }
+
+  #[test]
+  fn test_concurrent_claim_release() {
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 2000;
+
+    let bitmap = AtomicBitmap::new(THREADS / 2);
+    let owners: Vec<AtomicUsize> = (0..THREADS / 2).map(|_| AtomicUsize::new(0)).collect();
+
+    thread::scope(|scope| {
+      for id in 1..=THREADS {
+        let (bitmap, owners) = (&bitmap, &owners);
+        scope.spawn(move || {
+          for round in 0..ROUNDS {
+            let slot = round % owners.len();
+            if bitmap.try_claim(slot) {
+              assert_eq!(owners[slot].swap(id, Ordering::Relaxed), 0);
+              assert_eq!(owners[slot].swap(0, Ordering::Relaxed), id);
+              assert!(bitmap.release(slot));
+            }
+          }
+        });
+      }
+    });
+
+    assert_eq!(bitmap.count_claimed(), 0);
+  }
+}
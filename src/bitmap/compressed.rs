@@ -0,0 +1,977 @@
+//! Roaring-style compressed bitmap for sparse sets.
+
+use alloc::{
+  alloc::{
+    Allocator,
+    Global,
+  },
+  vec::Vec,
+};
+use core::slice;
+
+use super::{
+  Bitmap,
+  BitmapError,
+  BitmapOps,
+};
+
+/// Largest cardinality stored in an array container.
+const ARRAY_MAX: usize = 4096;
+
+/// Number of `u64` words in a bitmap container.
+const BITMAP_WORDS: usize = 1024;
+
+/// Largest number of runs a container can hold.
+const RUNS_MAX: usize = 32768;
+
+/// Magic bytes opening the serialized form.
+const MAGIC: [u8; 4] = *b"HCB1";
+
+/// Storage for the low 16 bits of the values sharing one key.
+#[derive(Debug, Clone)]
+enum Container<A>
+where
+  A: Allocator,
+{
+  /// Sorted low halves, used for up to [`ARRAY_MAX`] values.
+  Array(Vec<u16, A>),
+  /// One bit per low half, with the number of set bits.
+  Bitmap(Vec<u64, A>, usize),
+  /// Sorted, non-adjacent inclusive `(start, last)` ranges.
+  Run(Vec<(u16, u16), A>),
+}
+
+impl<A> Container<A>
+where
+  A: Allocator + Clone,
+{
+  fn len(&self) -> usize {
+    match self {
+      Self::Array(values) => values.len(),
+      Self::Bitmap(_, count) => *count,
+      Self::Run(runs) => runs
+        .iter()
+        .map(|&(start, last)| (last - start) as usize + 1)
+        .sum(),
+    }
+  }
+
+  fn contains(&self, low: u16) -> bool {
+    match self {
+      Self::Array(values) => values.binary_search(&low).is_ok(),
+      Self::Bitmap(words, _) => words[low as usize / 64] & (1 << (low % 64)) != 0,
+      Self::Run(runs) => {
+        let index = runs.partition_point(|&(_, last)| last < low);
+        index < runs.len() && runs[index].0 <= low
+      }
+    }
+  }
+
+  /// Add `low`, returning whether it was absent.
+  fn insert(&mut self, low: u16, allocator: &A) -> bool {
+    match self {
+      Self::Array(values) => {
+        let Err(index) = values.binary_search(&low) else {
+          return false;
+        };
+        if values.len() == ARRAY_MAX {
+          *self = Self::to_bitmap(self.iter(), allocator);
+          return self.insert(low, allocator);
+        }
+        values.insert(index, low);
+        true
+      }
+      Self::Bitmap(words, count) => {
+        let (word, mask) = (&mut words[low as usize / 64], 1 << (low % 64));
+        if *word & mask != 0 {
+          return false;
+        }
+        *word |= mask;
+        *count += 1;
+        true
+      }
+      Self::Run(runs) => {
+        let index = runs.partition_point(|&(_, last)| last < low);
+        if index < runs.len() && runs[index].0 <= low {
+          return false;
+        }
+        let joins_prev = index > 0 && runs[index - 1].1 as u32 + 1 == low as u32;
+        let joins_next = index < runs.len() && runs[index].0 as u32 == low as u32 + 1;
+        match (joins_prev, joins_next) {
+          (true, true) => {
+            runs[index - 1].1 = runs[index].1;
+            runs.remove(index);
+          }
+          (true, false) => runs[index - 1].1 = low,
+          (false, true) => runs[index].0 = low,
+          (false, false) => runs.insert(index, (low, low)),
+        }
+        true
+      }
+    }
+  }
+
+  /// Remove `low`, returning whether it was present.
+  fn remove(&mut self, low: u16, allocator: &A) -> bool {
+    match self {
+      Self::Array(values) => match values.binary_search(&low) {
+        Ok(index) => {
+          values.remove(index);
+          true
+        }
+        Err(_) => false,
+      },
+      Self::Bitmap(words, count) => {
+        let (word, mask) = (&mut words[low as usize / 64], 1 << (low % 64));
+        if *word & mask == 0 {
+          return false;
+        }
+        *word &= !mask;
+        *count -= 1;
+        if *count <= ARRAY_MAX {
+          *self = Self::to_array(self.iter(), allocator);
+        }
+        true
+      }
+      Self::Run(runs) => {
+        let index = runs.partition_point(|&(_, last)| last < low);
+        if index == runs.len() || runs[index].0 > low {
+          return false;
+        }
+        let (start, last) = runs[index];
+        if start == last {
+          runs.remove(index);
+        } else if low == start {
+          runs[index].0 += 1;
+        } else if low == last {
+          runs[index].1 -= 1;
+        } else {
+          runs[index].1 = low - 1;
+          runs.insert(index + 1, (low + 1, last));
+        }
+        true
+      }
+    }
+  }
+
+  fn iter(&self) -> ContainerIter<'_> {
+    match self {
+      Self::Array(values) => ContainerIter::Array(values.iter()),
+      Self::Bitmap(words, _) => ContainerIter::Bitmap {
+        words,
+        index: 0,
+        current: words[0],
+      },
+      Self::Run(runs) => ContainerIter::Run {
+        runs: runs.iter(),
+        next: 1,
+        last: 0,
+      },
+    }
+  }
+
+  fn to_array(values: ContainerIter<'_>, allocator: &A) -> Self {
+    let mut array = Vec::new_in(allocator.clone());
+    array.extend(values);
+    Self::Array(array)
+  }
+
+  fn to_bitmap(values: ContainerIter<'_>, allocator: &A) -> Self {
+    let mut words = Vec::with_capacity_in(BITMAP_WORDS, allocator.clone());
+    words.resize(BITMAP_WORDS, 0u64);
+    let mut count = 0;
+    for low in values {
+      words[low as usize / 64] |= 1 << (low % 64);
+      count += 1;
+    }
+    Self::Bitmap(words, count)
+  }
+
+  fn to_runs(values: ContainerIter<'_>, allocator: &A) -> Self {
+    let mut runs: Vec<(u16, u16), A> = Vec::new_in(allocator.clone());
+    for low in values {
+      match runs.last_mut() {
+        Some((_, last)) if *last as u32 + 1 == low as u32 => *last = low,
+        _ => runs.push((low, low)),
+      }
+    }
+    Self::Run(runs)
+  }
+
+  /// Number of runs of consecutive values.
+  fn run_count(&self) -> usize {
+    match self {
+      Self::Run(runs) => runs.len(),
+      _ => {
+        let mut previous = None;
+        let mut runs = 0;
+        for low in self.iter() {
+          if previous.is_none_or(|p: u16| p as u32 + 1 != low as u32) {
+            runs += 1;
+          }
+          previous = Some(low);
+        }
+        runs
+      }
+    }
+  }
+
+  /// Switch to whichever representation is smallest.
+  fn optimize(&mut self, allocator: &A) {
+    let len = self.len();
+    let runs = self.run_count();
+    let run_bytes = 4 * runs;
+    let dense_bytes = if len <= ARRAY_MAX {
+      2 * len
+    } else {
+      8 * BITMAP_WORDS
+    };
+    let optimized = if run_bytes < dense_bytes {
+      if matches!(self, Self::Run(_)) {
+        return;
+      }
+      Self::to_runs(self.iter(), allocator)
+    } else if len <= ARRAY_MAX {
+      if matches!(self, Self::Array(_)) {
+        return;
+      }
+      Self::to_array(self.iter(), allocator)
+    } else {
+      if matches!(self, Self::Bitmap(..)) {
+        return;
+      }
+      Self::to_bitmap(self.iter(), allocator)
+    };
+    *self = optimized;
+  }
+}
+
+/// Iterator over the low halves stored in one container.
+#[derive(Debug, Clone)]
+enum ContainerIter<'a> {
+  Array(slice::Iter<'a, u16>),
+  Bitmap {
+    words: &'a [u64],
+    index: usize,
+    current: u64,
+  },
+  Run {
+    runs: slice::Iter<'a, (u16, u16)>,
+    next: u32,
+    last: u32,
+  },
+}
+
+impl Iterator for ContainerIter<'_> {
+  type Item = u16;
+
+  fn next(&mut self) -> Option<u16> {
+    match self {
+      Self::Array(values) => values.next().copied(),
+      Self::Bitmap {
+        words,
+        index,
+        current,
+      } => {
+        while *current == 0 {
+          *index += 1;
+          *current = *words.get(*index)?;
+        }
+        let bit = current.trailing_zeros() as usize;
+        *current &= *current - 1;
+        Some((*index * 64 + bit) as u16)
+      }
+      Self::Run { runs, next, last } => {
+        if *next > *last {
+          let &(start, end) = runs.next()?;
+          (*next, *last) = (start as u32, end as u32);
+        }
+        *next += 1;
+        Some((*next - 1) as u16)
+      }
+    }
+  }
+}
+
+/// Container for the values whose high 16 bits equal `key`.
+#[derive(Debug, Clone)]
+struct Block<A>
+where
+  A: Allocator,
+{
+  key: u16,
+  container: Container<A>,
+}
+
+/// A compressed set of `u32` values in the style of Roaring bitmaps.
+///
+/// Values are grouped into blocks of 65536 by their high 16 bits. Each
+/// block stores its low halves as a sorted array while it holds at most
+/// 4096 values and as a 1024-word bitmap above that.
+/// [`optimize`](Self::optimize) additionally turns blocks into run-length
+/// encoded ranges where that is smaller. Memory use thus scales with the
+/// number and clustering of values rather than with the largest value.
+///
+/// # Serialization format
+///
+/// [`serialize_into`](Self::serialize_into) writes the following layout, with
+/// every integer little-endian:
+///
+/// | Field            | Type        | Notes                                  |
+/// |------------------|-------------|----------------------------------------|
+/// | magic            | `[u8; 4]`   | `b"HCB1"`                              |
+/// | container count  | `u32`       |                                        |
+/// | containers       | see below   | strictly ascending by key              |
+///
+/// Each container starts with its key (`u16`, the high 16 bits of every
+/// value in it) and a kind byte, followed by a kind-specific body:
+///
+/// | Kind | Name   | Body                                                        |
+/// |------|--------|-------------------------------------------------------------|
+/// | `0`  | array  | `u32` count `n` in `1..=4096`, then `n` strictly ascending `u16` low halves |
+/// | `1`  | bitmap | 1024 `u64` words; bit `i` of word `j` is low half `64 * j + i` |
+/// | `2`  | run    | `u32` count `r` in `1..=32768`, then `r` pairs of `u16` start and `u16` length minus one, ascending with a gap between consecutive runs |
+///
+/// Empty containers are never written. [`try_deserialize_in`](Self::try_deserialize_in)
+/// rejects input that does not follow these rules.
+#[derive(Debug, Clone)]
+pub struct CompressedBitmap<A = Global>
+where
+  A: Allocator + Clone,
+{
+  /// Allocator used for the block list and containers.
+  allocator: A,
+  /// Non-empty blocks, sorted by key.
+  blocks: Vec<Block<A>, A>,
+}
+
+impl<A> CompressedBitmap<A>
+where
+  A: Allocator + Clone,
+{
+  /// Create an empty bitmap in the given allocator.
+  pub fn new_in(allocator: A) -> Self {
+    Self {
+      blocks: Vec::new_in(allocator.clone()),
+      allocator,
+    }
+  }
+
+  /// Build a compressed copy of the set bits of a dense bitmap.
+  ///
+  /// Panics if a set bit lies beyond `u32::MAX`.
+  pub fn from_dense_in<D>(dense: &D, allocator: A) -> Self
+  where
+    D: BitmapOps + ?Sized,
+  {
+    let mut bitmap = Self::new_in(allocator);
+    bitmap.union_with(dense);
+    bitmap
+  }
+}
+
+impl CompressedBitmap<Global> {
+  /// Create an empty bitmap using the global allocator.
+  pub fn new() -> Self {
+    Self::new_in(Global)
+  }
+}
+
+impl Default for CompressedBitmap<Global> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<A> CompressedBitmap<A>
+where
+  A: Allocator + Clone,
+{
+  fn find(&self, key: u16) -> Result<usize, usize> {
+    self.blocks.binary_search_by_key(&key, |block| block.key)
+  }
+
+  /// Number of values in the set.
+  pub fn len(&self) -> u64 {
+    self
+      .blocks
+      .iter()
+      .map(|block| block.container.len() as u64)
+      .sum()
+  }
+
+  /// Whether the set is empty.
+  pub fn is_empty(&self) -> bool {
+    self.blocks.is_empty()
+  }
+
+  /// Whether `value` is in the set.
+  pub fn contains(&self, value: u32) -> bool {
+    let (key, low) = split(value);
+    self
+      .find(key)
+      .is_ok_and(|index| self.blocks[index].container.contains(low))
+  }
+
+  /// Add `value`, returning whether it was absent.
+  pub fn insert(&mut self, value: u32) -> bool {
+    let (key, low) = split(value);
+    match self.find(key) {
+      Ok(index) => self.blocks[index].container.insert(low, &self.allocator),
+      Err(index) => {
+        let mut values = Vec::new_in(self.allocator.clone());
+        values.push(low);
+        self.blocks.insert(
+          index,
+          Block {
+            key,
+            container: Container::Array(values),
+          },
+        );
+        true
+      }
+    }
+  }
+
+  /// Remove `value`, returning whether it was present.
+  pub fn remove(&mut self, value: u32) -> bool {
+    let (key, low) = split(value);
+    let Ok(index) = self.find(key) else {
+      return false;
+    };
+    let removed = self.blocks[index].container.remove(low, &self.allocator);
+    if self.blocks[index].container.len() == 0 {
+      self.blocks.remove(index);
+    }
+    removed
+  }
+
+  /// Remove every value.
+  pub fn clear(&mut self) {
+    self.blocks.clear();
+  }
+
+  /// Iterate over the values in ascending order.
+  pub fn iter(&self) -> Iter<'_, A> {
+    Iter {
+      blocks: self.blocks.iter(),
+      current: None,
+    }
+  }
+
+  /// Keep only the values for which `keep` returns `true`.
+  pub fn retain(&mut self, mut keep: impl FnMut(u32) -> bool) {
+    let allocator = &self.allocator;
+    self.blocks.retain_mut(|block| {
+      let base = (block.key as u32) << 16;
+      let kept = block
+        .container
+        .iter()
+        .filter(|&low| keep(base | low as u32));
+      let mut container = Container::Array(Vec::new_in(allocator.clone()));
+      for low in kept {
+        container.insert(low, allocator);
+      }
+      block.container = container;
+      block.container.len() != 0
+    });
+  }
+
+  /// Convert each block to its most compact representation, including
+  /// run-length encoding.
+  pub fn optimize(&mut self) {
+    for block in &mut self.blocks {
+      block.container.optimize(&self.allocator);
+    }
+  }
+
+  /// Add every bit set in `dense`.
+  ///
+  /// Panics if a set bit lies beyond `u32::MAX`.
+  pub fn union_with<D>(&mut self, dense: &D)
+  where
+    D: BitmapOps + ?Sized,
+  {
+    for index in dense.iter_ones() {
+      self.insert(to_value(index));
+    }
+  }
+
+  /// Keep only values whose bit is set in `dense`.
+  pub fn intersect_with<D>(&mut self, dense: &D)
+  where
+    D: BitmapOps + ?Sized,
+  {
+    self.retain(|value| dense_contains(dense, value));
+  }
+
+  /// Remove every value whose bit is set in `dense`.
+  pub fn difference_with<D>(&mut self, dense: &D)
+  where
+    D: BitmapOps + ?Sized,
+  {
+    self.retain(|value| !dense_contains(dense, value));
+  }
+
+  /// Flip membership of every value whose bit is set in `dense`.
+  ///
+  /// Panics if a set bit lies beyond `u32::MAX`.
+  pub fn symmetric_difference_with<D>(&mut self, dense: &D)
+  where
+    D: BitmapOps + ?Sized,
+  {
+    for index in dense.iter_ones() {
+      let value = to_value(index);
+      if !self.remove(value) {
+        self.insert(value);
+      }
+    }
+  }
+
+  /// Whether every value is set in `dense`.
+  pub fn is_subset<D>(&self, dense: &D) -> bool
+  where
+    D: BitmapOps + ?Sized,
+  {
+    self.iter().all(|value| dense_contains(dense, value))
+  }
+
+  /// Whether no value is set in `dense`.
+  pub fn is_disjoint<D>(&self, dense: &D) -> bool
+  where
+    D: BitmapOps + ?Sized,
+  {
+    !self.iter().any(|value| dense_contains(dense, value))
+  }
+
+  /// Try to expand the set into a dense bitmap of `len` bits.
+  ///
+  /// Fails with [`BitmapError::OutOfBounds`] if a value does not fit.
+  pub fn try_to_dense_in<B>(
+    &self,
+    allocator: B,
+    len: usize,
+  ) -> Result<Bitmap<'static, B>, BitmapError>
+  where
+    B: Allocator,
+  {
+    let mut dense = Bitmap::try_new_in(allocator, len)?;
+    for value in self.iter() {
+      dense.try_set(value as usize)?;
+    }
+    Ok(dense)
+  }
+
+  /// Expand the set into a dense bitmap, panicking on failure.
+  pub fn to_dense_in<B>(&self, allocator: B, len: usize) -> Bitmap<'static, B>
+  where
+    B: Allocator,
+  {
+    self
+      .try_to_dense_in(allocator, len)
+      .expect("Failed to expand CompressedBitmap")
+  }
+
+  /// Number of bytes [`serialize_into`](Self::serialize_into) writes.
+  pub fn serialized_size(&self) -> usize {
+    8 + self
+      .blocks
+      .iter()
+      .map(|block| {
+        3 + match &block.container {
+          Container::Array(values) => 4 + 2 * values.len(),
+          Container::Bitmap(..) => 8 * BITMAP_WORDS,
+          Container::Run(runs) => 4 + 4 * runs.len(),
+        }
+      })
+      .sum::<usize>()
+  }
+
+  /// Append the portable serialized form described in the
+  /// [type documentation](Self) to `out`.
+  pub fn serialize_into<B>(&self, out: &mut Vec<u8, B>)
+  where
+    B: Allocator,
+  {
+    out.reserve(self.serialized_size());
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&(self.blocks.len() as u32).to_le_bytes());
+    for block in &self.blocks {
+      out.extend_from_slice(&block.key.to_le_bytes());
+      match &block.container {
+        Container::Array(values) => {
+          out.push(0);
+          out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+          for low in values {
+            out.extend_from_slice(&low.to_le_bytes());
+          }
+        }
+        Container::Bitmap(words, _) => {
+          out.push(1);
+          for word in words {
+            out.extend_from_slice(&word.to_le_bytes());
+          }
+        }
+        Container::Run(runs) => {
+          out.push(2);
+          out.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+          for &(start, last) in runs {
+            out.extend_from_slice(&start.to_le_bytes());
+            out.extend_from_slice(&(last - start).to_le_bytes());
+          }
+        }
+      }
+    }
+  }
+
+  /// Try to read a bitmap written by [`serialize_into`](Self::serialize_into).
+  ///
+  /// Fails with [`BitmapError::InvalidData`] on malformed input.
+  pub fn try_deserialize_in(bytes: &[u8], allocator: A) -> Result<Self, BitmapError> {
+    let mut reader = Reader { bytes };
+    if reader.take(4)? != MAGIC {
+      return Err(BitmapError::InvalidData);
+    }
+    let count = reader.u32()? as usize;
+    let mut bitmap = Self::new_in(allocator);
+    bitmap
+      .blocks
+      .try_reserve(count.min(bytes.len()))
+      .map_err(|_| BitmapError::AllocError)?;
+
+    for _ in 0..count {
+      let key = reader.u16()?;
+      if bitmap.blocks.last().is_some_and(|block| block.key >= key) {
+        return Err(BitmapError::InvalidData);
+      }
+      let container = match reader.u8()? {
+        0 => {
+          let len = reader.u32()? as usize;
+          if len == 0 || len > ARRAY_MAX {
+            return Err(BitmapError::InvalidData);
+          }
+          let mut values = Vec::new_in(bitmap.allocator.clone());
+          values
+            .try_reserve_exact(len)
+            .map_err(|_| BitmapError::AllocError)?;
+          for _ in 0..len {
+            let low = reader.u16()?;
+            if values.last().is_some_and(|&previous| previous >= low) {
+              return Err(BitmapError::InvalidData);
+            }
+            values.push(low);
+          }
+          Container::Array(values)
+        }
+        1 => {
+          let mut words = Vec::new_in(bitmap.allocator.clone());
+          words
+            .try_reserve_exact(BITMAP_WORDS)
+            .map_err(|_| BitmapError::AllocError)?;
+          let mut count = 0;
+          for _ in 0..BITMAP_WORDS {
+            let word = reader.u64()?;
+            count += word.count_ones() as usize;
+            words.push(word);
+          }
+          if count == 0 {
+            return Err(BitmapError::InvalidData);
+          }
+          Container::Bitmap(words, count)
+        }
+        2 => {
+          let len = reader.u32()? as usize;
+          if len == 0 || len > RUNS_MAX {
+            return Err(BitmapError::InvalidData);
+          }
+          let mut runs: Vec<(u16, u16), A> = Vec::new_in(bitmap.allocator.clone());
+          runs
+            .try_reserve_exact(len)
+            .map_err(|_| BitmapError::AllocError)?;
+          for _ in 0..len {
+            let start = reader.u16()?;
+            let last = start
+              .checked_add(reader.u16()?)
+              .ok_or(BitmapError::InvalidData)?;
+            if runs
+              .last()
+              .is_some_and(|&(_, previous)| previous as u32 + 1 >= start as u32)
+            {
+              return Err(BitmapError::InvalidData);
+            }
+            runs.push((start, last));
+          }
+          Container::Run(runs)
+        }
+        _ => return Err(BitmapError::InvalidData),
+      };
+      bitmap.blocks.push(Block { key, container });
+    }
+
+    if !reader.bytes.is_empty() {
+      return Err(BitmapError::InvalidData);
+    }
+    Ok(bitmap)
+  }
+}
+
+impl CompressedBitmap<Global> {
+  /// Try to read a serialized bitmap using the global allocator.
+  pub fn try_deserialize(bytes: &[u8]) -> Result<Self, BitmapError> {
+    Self::try_deserialize_in(bytes, Global)
+  }
+}
+
+impl<A, B> PartialEq<CompressedBitmap<B>> for CompressedBitmap<A>
+where
+  A: Allocator + Clone,
+  B: Allocator + Clone,
+{
+  fn eq(&self, other: &CompressedBitmap<B>) -> bool {
+    self.iter().eq(other.iter())
+  }
+}
+
+impl<A> Eq for CompressedBitmap<A> where A: Allocator + Clone {}
+
+impl<A> Extend<u32> for CompressedBitmap<A>
+where
+  A: Allocator + Clone,
+{
+  fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+    for value in iter {
+      self.insert(value);
+    }
+  }
+}
+
+impl FromIterator<u32> for CompressedBitmap<Global> {
+  fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+    let mut bitmap = Self::new();
+    bitmap.extend(iter);
+    bitmap
+  }
+}
+
+impl<'a, A> IntoIterator for &'a CompressedBitmap<A>
+where
+  A: Allocator + Clone,
+{
+  type Item = u32;
+  type IntoIter = Iter<'a, A>;
+
+  fn into_iter(self) -> Iter<'a, A> {
+    self.iter()
+  }
+}
+
+/// Iterator over the values of a [`CompressedBitmap`] in ascending order.
+#[derive(Debug, Clone)]
+pub struct Iter<'a, A>
+where
+  A: Allocator,
+{
+  /// Blocks not yet started.
+  blocks: slice::Iter<'a, Block<A>>,
+  /// High bits and remaining values of the current block.
+  current: Option<(u32, ContainerIter<'a>)>,
+}
+
+impl<A> Iterator for Iter<'_, A>
+where
+  A: Allocator + Clone,
+{
+  type Item = u32;
+
+  fn next(&mut self) -> Option<u32> {
+    loop {
+      if let Some((base, values)) = &mut self.current
+        && let Some(low) = values.next()
+      {
+        return Some(*base | low as u32);
+      }
+      let block = self.blocks.next()?;
+      self.current = Some(((block.key as u32) << 16, block.container.iter()));
+    }
+  }
+}
+
+/// Split a value into its block key and low half.
+fn split(value: u32) -> (u16, u16) {
+  ((value >> 16) as u16, value as u16)
+}
+
+/// Convert a dense bit index to a value, panicking if it does not fit.
+fn to_value(index: usize) -> u32 {
+  u32::try_from(index).expect("CompressedBitmap values must fit in u32")
+}
+
+/// Whether `value` is set in `dense`, treating bits past its end as clear.
+fn dense_contains<D>(dense: &D, value: u32) -> bool
+where
+  D: BitmapOps + ?Sized,
+{
+  dense.try_get(value as usize).unwrap_or(false)
+}
+
+/// Little-endian cursor over serialized input.
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], BitmapError> {
+    if self.bytes.len() < len {
+      return Err(BitmapError::InvalidData);
+    }
+    let (head, tail) = self.bytes.split_at(len);
+    self.bytes = tail;
+    Ok(head)
+  }
+
+  fn u8(&mut self) -> Result<u8, BitmapError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, BitmapError> {
+    Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+  }
+
+  fn u32(&mut self) -> Result<u32, BitmapError> {
+    Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+  }
+
+  fn u64(&mut self) -> Result<u64, BitmapError> {
+    Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_insert_remove_contains() {
+    let mut set = CompressedBitmap::new();
+    assert!(set.is_empty());
+    assert!(set.insert(7));
+    assert!(!set.insert(7));
+    assert!(set.insert(3_000_000_000));
+    assert!(set.insert(u32::MAX));
+    assert!(set.contains(3_000_000_000));
+    assert!(!set.contains(8));
+    assert_eq!(set.len(), 3);
+    assert_eq!(set.iter().collect::<Vec<_>>(), [7, 3_000_000_000, u32::MAX]);
+
+    assert!(set.remove(7));
+    assert!(!set.remove(7));
+    assert_eq!(set.blocks.len(), 2);
+  }
+
+  #[test]
+  fn test_array_bitmap_conversion() {
+    let mut set = CompressedBitmap::new();
+    for value in (0..20_000).step_by(2) {
+      set.insert(value);
+    }
+    assert!(matches!(set.blocks[0].container, Container::Bitmap(..)));
+    assert_eq!(set.len(), 10_000);
+
+    for value in (0..20_000).step_by(2).skip(ARRAY_MAX) {
+      set.remove(value);
+    }
+    assert!(matches!(set.blocks[0].container, Container::Array(_)));
+    assert_eq!(set.len(), ARRAY_MAX as u64);
+    assert_eq!(set.iter().last(), Some(2 * (ARRAY_MAX as u32 - 1)));
+  }
+
+  #[test]
+  fn test_runs() {
+    let mut set: CompressedBitmap = (100..60_000).chain(70_000..70_010).collect();
+    set.optimize();
+    assert!(matches!(set.blocks[0].container, Container::Run(_)));
+    assert!(matches!(set.blocks[1].container, Container::Run(_)));
+
+    assert!(set.remove(500));
+    assert!(!set.contains(500));
+    assert!(set.remove(100));
+    assert!(set.insert(99));
+    assert!(set.insert(500));
+    assert!(set.insert(60_000));
+    assert!(!set.insert(101));
+    assert_eq!(set.len(), 59_900 + 1 + 10);
+
+    let expected: CompressedBitmap = (99..=60_000)
+      .filter(|&v| v != 100)
+      .chain(70_000..70_010)
+      .collect();
+    assert_eq!(set, expected);
+  }
+
+  #[test]
+  fn test_dense_algebra() {
+    let mut dense = Bitmap::new(300);
+    dense.set_range(100..200);
+    let mut set: CompressedBitmap = [5, 150, 250, 1_000_000].into_iter().collect();
+
+    assert!(!set.is_subset(&dense));
+    let mut both = set.clone();
+    both.intersect_with(&dense);
+    assert_eq!(both.iter().collect::<Vec<_>>(), [150]);
+    assert!(both.is_subset(&dense));
+
+    let mut outside = set.clone();
+    outside.difference_with(&dense);
+    assert!(outside.is_disjoint(&dense));
+    assert_eq!(outside.len(), 3);
+
+    set.symmetric_difference_with(&dense);
+    assert_eq!(set.len(), 3 + 99);
+    assert!(!set.contains(150));
+
+    set.union_with(&dense);
+    assert_eq!(set.len(), 103);
+    assert!(set.try_to_dense_in(Global, 300).is_err());
+    set.remove(1_000_000);
+    assert_eq!(set.to_dense_in(Global, 300).count_ones(), 102);
+
+    let round_trip = CompressedBitmap::from_dense_in(&dense, Global);
+    assert_eq!(round_trip.to_dense_in(Global, 300), dense);
+  }
+
+  #[test]
+  fn test_serialization() {
+    let mut set: CompressedBitmap = (0..10)
+      .chain((70_000..80_000).step_by(2))
+      .chain(200_000..250_000)
+      .collect();
+    set.optimize();
+
+    let mut bytes = Vec::new();
+    set.serialize_into(&mut bytes);
+    assert_eq!(bytes.len(), set.serialized_size());
+    assert_eq!(&bytes[..4], b"HCB1");
+    assert_eq!(CompressedBitmap::try_deserialize(&bytes).unwrap(), set);
+
+    let mut empty = Vec::new();
+    CompressedBitmap::new().serialize_into(&mut empty);
+    assert_eq!(empty, [b'H', b'C', b'B', b'1', 0, 0, 0, 0]);
+
+    assert!(CompressedBitmap::try_deserialize(&bytes[..bytes.len() - 1]).is_err());
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(CompressedBitmap::try_deserialize(&trailing).is_err());
+    let mut unsorted = empty.clone();
+    unsorted[4] = 1;
+    unsorted.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 5, 0, 5, 0]);
+    assert!(CompressedBitmap::try_deserialize(&unsorted).is_err());
+    unsorted[17] = 6;
+    assert_eq!(
+      CompressedBitmap::try_deserialize(&unsorted)
+        .unwrap()
+        .iter()
+        .collect::<Vec<_>>(),
+      [5, 6]
+    );
+  }
+}
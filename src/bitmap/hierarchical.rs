@@ -0,0 +1,414 @@
+//! Bitmap with layered word summaries for fast searches.
+
+use alloc::{
+  alloc::{
+    Allocator,
+    Global,
+  },
+  vec::Vec,
+};
+
+use super::{
+  Bitmap,
+  BitmapError,
+  WORD_BITS,
+};
+
+/// A [`Bitmap`] with summaries recording which words contain set bits and
+/// which contain clear bits.
+///
+/// Each summary is a stack of bitmaps: the lowest flags words of the bits,
+/// and every level above flags the non-zero words of the one below, until a
+/// level fits in a single word. A search climbs the levels until it finds a
+/// flagged word and then descends, so finding a set or clear bit reads at
+/// most two words per level, and the number of levels grows with the
+/// logarithm of the length in base [`usize::BITS`].
+#[derive(Debug)]
+pub struct HierarchicalBitmap<'map, A = Global>
+where
+  A: Allocator + Clone,
+{
+  /// The bits themselves.
+  bits: Bitmap<'map, A>,
+  /// Flags the words of `bits` that have a set bit.
+  nonempty: Summary<'map, A>,
+  /// Flags the words of `bits` that have a clear bit below `len`.
+  nonfull: Summary<'map, A>,
+}
+
+impl<'map, A> HierarchicalBitmap<'map, A>
+where
+  A: Allocator + Clone,
+{
+  /// Try to create a bitmap of `size` clear bits in the given allocator.
+  pub fn try_new_in(allocator: A, size: usize) -> Result<Self, BitmapError> {
+    let words = size.div_ceil(WORD_BITS);
+    let nonempty = Summary::try_build(&allocator, words, |_| false)?;
+    let nonfull = Summary::try_build(&allocator, words, |_| true)?;
+    let bits = Bitmap::try_new_in(allocator, size)?;
+    Ok(Self {
+      bits,
+      nonempty,
+      nonfull,
+    })
+  }
+
+  /// Create a bitmap, panicking on failure.
+  pub fn new_in(allocator: A, size: usize) -> Self {
+    Self::try_new_in(allocator, size).expect("Failed to create HierarchicalBitmap")
+  }
+}
+
+impl<'map> HierarchicalBitmap<'map, Global> {
+  /// Create a bitmap using the global allocator.
+  pub fn new(size: usize) -> Self {
+    Self::new_in(Global, size)
+  }
+}
+
+impl<'map, A> HierarchicalBitmap<'map, A>
+where
+  A: Allocator + Clone,
+{
+  /// The underlying bits, for queries the summaries do not speed up.
+  pub fn as_bitmap(&self) -> &Bitmap<'map, A> {
+    &self.bits
+  }
+
+  /// Number of bits in the bitmap.
+  pub fn len(&self) -> usize {
+    self.bits.len()
+  }
+
+  /// Whether the bitmap holds no bits.
+  pub fn is_empty(&self) -> bool {
+    self.bits.is_empty()
+  }
+
+  /// Try to get the bit at the given index.
+  pub fn try_get(&self, index: usize) -> Result<bool, BitmapError> {
+    self.bits.try_get(index)
+  }
+
+  /// Get the bit at the given index, panicking on out-of-bounds.
+  pub fn get(&self, index: usize) -> bool {
+    self.bits.get(index)
+  }
+
+  /// Try to set the bit at the given index.
+  pub fn try_set(&mut self, index: usize) -> Result<(), BitmapError> {
+    self.bits.try_set(index)?;
+    self.refresh(index / WORD_BITS);
+    Ok(())
+  }
+
+  /// Set the bit at the given index, panicking on out-of-bounds.
+  pub fn set(&mut self, index: usize) {
+    self.try_set(index).expect("Bitmap index out of bounds");
+  }
+
+  /// Try to clear the bit at the given index.
+  pub fn try_clear(&mut self, index: usize) -> Result<(), BitmapError> {
+    self.bits.try_clear(index)?;
+    self.refresh(index / WORD_BITS);
+    Ok(())
+  }
+
+  /// Clear the bit at the given index, panicking on out-of-bounds.
+  pub fn clear(&mut self, index: usize) {
+    self.try_clear(index).expect("Bitmap index out of bounds");
+  }
+
+  /// Try to resize the bitmap to a new bit count.
+  ///
+  /// The summaries are rebuilt for the new size. On failure the bitmap is
+  /// left unchanged.
+  pub fn try_resize(&mut self, new_size: usize) -> Result<(), BitmapError> {
+    let new_words = new_size.div_ceil(WORD_BITS);
+    let word = |index: usize| {
+      let bits = self.bits.map.get(index).copied().unwrap_or(0);
+      (bits & valid_mask(index, new_size), valid_mask(index, new_size))
+    };
+
+    let allocator = &self.bits.allocator;
+    let nonempty = Summary::try_build(allocator, new_words, |index| word(index).0 != 0)?;
+    let nonfull = Summary::try_build(allocator, new_words, |index| {
+      let (bits, valid) = word(index);
+      bits != valid
+    })?;
+    self.bits.try_resize(new_size)?;
+
+    self.nonempty = nonempty;
+    self.nonfull = nonfull;
+    Ok(())
+  }
+
+  /// Resize the bitmap, panicking on failure.
+  pub fn resize(&mut self, new_size: usize) {
+    self.try_resize(new_size).expect("Failed to resize HierarchicalBitmap");
+  }
+
+  /// Index of the lowest set bit.
+  pub fn first_set(&self) -> Option<usize> {
+    self.next_set(0)
+  }
+
+  /// Index of the lowest clear bit.
+  pub fn first_clear(&self) -> Option<usize> {
+    self.next_clear(0)
+  }
+
+  /// Index of the lowest set bit at or after `from`.
+  pub fn next_set(&self, from: usize) -> Option<usize> {
+    self.search(from, &self.nonempty, |word| word)
+  }
+
+  /// Index of the lowest clear bit at or after `from`.
+  pub fn next_clear(&self, from: usize) -> Option<usize> {
+    self.search(from, &self.nonfull, |word| !word)
+  }
+
+  /// Index of the highest set bit.
+  pub fn last_set(&self) -> Option<usize> {
+    let word = self.nonempty.last()?;
+    let bits = self.bits.map[word];
+    Some(word * WORD_BITS + (WORD_BITS - 1 - bits.leading_zeros() as usize))
+  }
+
+  /// Look in the word holding `from`, then jump to the next word flagged
+  /// in `summary`.
+  fn search(
+    &self,
+    from: usize,
+    summary: &Summary<'map, A>,
+    view: impl Fn(usize) -> usize,
+  ) -> Option<usize> {
+    if from >= self.len() {
+      return None;
+    }
+    let word = from / WORD_BITS;
+    let bits = view(self.bits.map[word]) & (usize::MAX << (from % WORD_BITS));
+    let (word, bits) = if bits != 0 {
+      (word, bits)
+    } else {
+      let next = summary.next(0, word + 1)?;
+      (next, view(self.bits.map[next]))
+    };
+    let found = word * WORD_BITS + bits.trailing_zeros() as usize;
+    (found < self.len()).then_some(found)
+  }
+
+  /// Recompute both summaries for `word`.
+  fn refresh(&mut self, word: usize) {
+    let bits = self.bits.map[word];
+    self.nonempty.update(word, bits != 0);
+    self.nonfull.update(word, bits != valid_mask(word, self.len()));
+  }
+}
+
+/// Mask of the bits of word `index` that lie below `len`.
+fn valid_mask(index: usize, len: usize) -> usize {
+  let start = index * WORD_BITS;
+  if start + WORD_BITS <= len {
+    usize::MAX
+  } else if start < len {
+    (1 << (len - start)) - 1
+  } else {
+    0
+  }
+}
+
+/// Stack of bitmaps flagging words of a [`HierarchicalBitmap`].
+///
+/// Bit `i` of level 0 flags word `i` of the bits. Bit `i` of every higher
+/// level is set when word `i` of the level below is non-zero. The top level
+/// holds at most one word.
+#[derive(Debug)]
+struct Summary<'map, A>
+where
+  A: Allocator + Clone,
+{
+  levels: Vec<Bitmap<'map, A>, A>,
+}
+
+impl<'map, A> Summary<'map, A>
+where
+  A: Allocator + Clone,
+{
+  /// Build a summary of `words` flags, where word `i` is flagged when
+  /// `flag(i)` holds.
+  fn try_build(
+    allocator: &A,
+    words: usize,
+    flag: impl Fn(usize) -> bool,
+  ) -> Result<Self, BitmapError> {
+    let mut levels = Vec::new_in(allocator.clone());
+    let mut level = Bitmap::try_new_in(allocator.clone(), words)?;
+    for index in (0..words).filter(|&index| flag(index)) {
+      level.set(index);
+    }
+
+    while level.len() > WORD_BITS {
+      let mut above = Bitmap::try_new_in(allocator.clone(), level.len().div_ceil(WORD_BITS))?;
+      for index in 0..above.len() {
+        if level.map[index] != 0 {
+          above.set(index);
+        }
+      }
+      levels
+        .try_reserve(1)
+        .map_err(|_| BitmapError::AllocError)?;
+      levels.push(core::mem::replace(&mut level, above));
+    }
+    levels
+      .try_reserve(1)
+      .map_err(|_| BitmapError::AllocError)?;
+    levels.push(level);
+    Ok(Self { levels })
+  }
+
+  /// Set or clear the flag of `index`, updating the levels above.
+  fn update(&mut self, mut index: usize, mut flag: bool) {
+    for bitmap in self.levels.iter_mut() {
+      if bitmap.get(index) == flag {
+        return;
+      }
+      if flag {
+        bitmap.set(index);
+      } else {
+        bitmap.clear(index);
+      }
+      index /= WORD_BITS;
+      flag = bitmap.map[index] != 0;
+    }
+  }
+
+  /// Lowest flagged index at or after `from` in `level`.
+  fn next(&self, level: usize, from: usize) -> Option<usize> {
+    let bitmap = &self.levels[level];
+    if from >= bitmap.len() {
+      return None;
+    }
+    let word = from / WORD_BITS;
+    let bits = bitmap.map[word] & (usize::MAX << (from % WORD_BITS));
+    let (word, bits) = if bits != 0 {
+      (word, bits)
+    } else if level + 1 < self.levels.len() {
+      let next = self.next(level + 1, word + 1)?;
+      (next, bitmap.map[next])
+    } else {
+      return None;
+    };
+    Some(word * WORD_BITS + bits.trailing_zeros() as usize)
+  }
+
+  /// Highest flagged index in level 0.
+  fn last(&self) -> Option<usize> {
+    let mut index = 0;
+    for bitmap in self.levels.iter().rev() {
+      let bits = *bitmap.map.get(index)?;
+      if bits == 0 {
+        return None;
+      }
+      index = index * WORD_BITS + (WORD_BITS - 1 - bits.leading_zeros() as usize);
+    }
+    Some(index)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_search_matches_flat_bitmap() {
+    let mut bitmap = HierarchicalBitmap::new(10_000);
+    assert_eq!(bitmap.first_set(), None);
+    assert_eq!(bitmap.first_clear(), Some(0));
+
+    for i in [5, 64, 4000, 9999] {
+      bitmap.set(i);
+    }
+    assert_eq!(bitmap.first_set(), Some(5));
+    assert_eq!(bitmap.next_set(6), Some(64));
+    assert_eq!(bitmap.next_set(65), Some(4000));
+    assert_eq!(bitmap.last_set(), Some(9999));
+
+    for i in 0..9000 {
+      bitmap.set(i);
+    }
+    assert_eq!(bitmap.first_clear(), Some(9000));
+    bitmap.clear(700);
+    assert_eq!(bitmap.first_clear(), Some(700));
+    assert_eq!(bitmap.next_clear(701), Some(9000));
+    assert_eq!(bitmap.next_clear(9999), None);
+
+    for from in (0..10_000).step_by(37) {
+      assert_eq!(bitmap.next_set(from), bitmap.as_bitmap().next_set(from));
+      assert_eq!(bitmap.next_clear(from), bitmap.as_bitmap().next_clear(from));
+    }
+  }
+
+  #[test]
+  fn test_search_across_levels() {
+    let size = 300_000;
+    let mut bitmap = HierarchicalBitmap::new(size);
+    assert_eq!(bitmap.nonempty.levels.len(), 3);
+    assert_eq!(bitmap.last_set(), None);
+
+    bitmap.set(299_999);
+    assert_eq!(bitmap.first_set(), Some(299_999));
+    assert_eq!(bitmap.last_set(), Some(299_999));
+    bitmap.set(4097);
+    assert_eq!(bitmap.next_set(1), Some(4097));
+    assert_eq!(bitmap.next_set(4098), Some(299_999));
+    bitmap.clear(299_999);
+    assert_eq!(bitmap.next_set(4098), None);
+    assert_eq!(bitmap.last_set(), Some(4097));
+
+    for i in 0..size {
+      bitmap.set(i);
+    }
+    assert_eq!(bitmap.first_clear(), None);
+    bitmap.clear(262_145);
+    assert_eq!(bitmap.first_clear(), Some(262_145));
+    bitmap.set(262_145);
+    assert_eq!(bitmap.first_clear(), None);
+
+    bitmap.resize(5_000);
+    assert_eq!(bitmap.nonfull.levels.len(), 2);
+    assert_eq!(bitmap.first_clear(), None);
+    bitmap.resize(400_000);
+    assert_eq!(bitmap.first_clear(), Some(5_000));
+    assert_eq!(bitmap.last_set(), Some(4_999));
+
+    for from in (0..400_000).step_by(997) {
+      assert_eq!(bitmap.next_set(from), bitmap.as_bitmap().next_set(from));
+      assert_eq!(bitmap.next_clear(from), bitmap.as_bitmap().next_clear(from));
+    }
+  }
+
+  #[test]
+  fn test_resize_keeps_summary() {
+    let mut bitmap = HierarchicalBitmap::new(100);
+    for i in 0..100 {
+      bitmap.set(i);
+    }
+    assert_eq!(bitmap.first_clear(), None);
+
+    bitmap.resize(130);
+    assert_eq!(bitmap.first_clear(), Some(100));
+    bitmap.resize(64);
+    assert_eq!(bitmap.first_clear(), None);
+    assert_eq!(bitmap.last_set(), Some(63));
+
+    bitmap.resize(70);
+    bitmap.set(69);
+    assert_eq!(bitmap.first_clear(), Some(64));
+    bitmap.resize(10);
+    assert_eq!(bitmap.next_set(5), Some(5));
+    bitmap.resize(0);
+    assert_eq!(bitmap.first_set(), None);
+    assert_eq!(bitmap.first_clear(), None);
+  }
+}
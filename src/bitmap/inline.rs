@@ -0,0 +1,116 @@
+//! Bitmap with inline, const-sized storage.
+
+use super::{
+  ops::{
+    Storage,
+    Token,
+  },
+  WORD_BITS,
+};
+
+/// Number of words an [`InlineBitmap`] of `bits` bits needs.
+pub const fn words_for(bits: usize) -> usize {
+  bits.div_ceil(WORD_BITS)
+}
+
+/// A fixed-length bitmap of `BITS` bits stored inline in `WORDS` words.
+///
+/// Ideally this would be `InlineBitmap<const BITS: usize>`, but stable const
+/// generics cannot size an array from an expression over a generic
+/// parameter: `[usize; BITS.div_ceil(usize::BITS as usize)]` needs the
+/// unfinished `generic_const_exprs` feature. The word count is therefore a
+/// second parameter that must be spelled out as [`words_for`]`(BITS)`. Any
+/// other value fails to compile. It defaults to one word, so maps of up to
+/// `usize::BITS` bits can be written `InlineBitmap<BITS>`. The query and
+/// mutation API comes from [`BitmapOps`](super::BitmapOps).
+///
+/// ```
+/// use heaped::bitmap::{
+///   words_for,
+///   BitmapOps,
+///   InlineBitmap,
+/// };
+///
+/// static EMPTY: InlineBitmap<1000, { words_for(1000) }> = InlineBitmap::new();
+///
+/// let mut flags = EMPTY;
+/// flags.set(999);
+/// assert_eq!(flags.first_set(), Some(999));
+///
+/// let small = InlineBitmap::<8>::new();
+/// assert_eq!(small.first_set(), None);
+/// ```
+///
+/// A word count that does not match `BITS` is rejected:
+///
+/// ```compile_fail
+/// use heaped::bitmap::InlineBitmap;
+///
+/// let flags = InlineBitmap::<1000, 2>::new();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InlineBitmap<const BITS: usize, const WORDS: usize = 1> {
+  /// Words holding the bitmap bits; bits at and past `BITS` are always zero.
+  words: [usize; WORDS],
+}
+
+impl<const BITS: usize, const WORDS: usize> InlineBitmap<BITS, WORDS> {
+  /// Create a bitmap with every bit clear.
+  pub const fn new() -> Self {
+    const {
+      assert!(
+        WORDS == words_for(BITS),
+        "InlineBitmap WORDS must equal words_for(BITS)"
+      )
+    };
+    Self {
+      words: [0; WORDS],
+    }
+  }
+}
+
+impl<const BITS: usize, const WORDS: usize> Default for InlineBitmap<BITS, WORDS> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const BITS: usize, const WORDS: usize> Storage for InlineBitmap<BITS, WORDS> {
+  fn bit_len(&self) -> usize {
+    BITS
+  }
+
+  fn raw_words(&self) -> &[usize] {
+    &self.words
+  }
+
+  fn raw_words_mut(&mut self, _: Token) -> &mut [usize] {
+    &mut self.words
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bitmap::BitmapOps;
+
+  #[test]
+  fn test_inline_bitmap() {
+    let mut small = InlineBitmap::<40>::new();
+    small.set_range(0..40);
+    assert_eq!(small.first_clear(), None);
+    assert_eq!(small.count_ones(), 40);
+    assert!(small.try_set(40).is_err());
+
+    let mut large = InlineBitmap::<130, { words_for(130) }>::default();
+    large.set(64);
+    large.set(129);
+    assert_eq!(large.iter_ones().rev().collect::<alloc::vec::Vec<_>>(), [129, 64]);
+    assert_eq!(large.rank(130), 2);
+    assert_eq!(large.select(1), Some(129));
+
+    let copy = large;
+    large.clear(129);
+    assert_ne!(copy, large);
+  }
+}
@@ -0,0 +1,1141 @@
+//! Bitmap structure for tracking fixed-size boolean flags.
+
+use core::{
+  hash::{
+    Hash,
+    Hasher,
+  },
+  ops::{
+    BitAnd,
+    BitAndAssign,
+    BitOr,
+    BitOrAssign,
+    BitXor,
+    BitXorAssign,
+    Not,
+    Range,
+  },
+  ptr::NonNull,
+};
+
+use alloc::{
+  alloc::{
+    Allocator,
+    Global,
+    Layout,
+  },
+  vec::Vec,
+};
+
+mod atomic;
+mod compressed;
+mod hierarchical;
+mod inline;
+mod ops;
+mod slice;
+
+pub use atomic::AtomicBitmap;
+pub use compressed::{
+  CompressedBitmap,
+  Iter as CompressedIter,
+};
+pub use hierarchical::HierarchicalBitmap;
+pub use inline::{
+  words_for,
+  InlineBitmap,
+};
+pub use ops::{
+  BitmapOps,
+  Ones,
+  Zeros,
+};
+use ops::{
+  count_between,
+  select_from,
+  Storage,
+  Token,
+};
+pub use slice::BitmapRef;
+
+/// Number of bits in one storage word.
+const WORD_BITS: usize = usize::BITS as usize;
+
+/// Number of words covered by one entry of a [`RankIndex`].
+const SUPERBLOCK_WORDS: usize = 8;
+
+#[derive(Debug)]
+/// Errors that can occur while operating on a [`Bitmap`].
+pub enum BitmapError {
+  /// Index was outside the bitmap bounds.
+  OutOfBounds,
+  /// Allocation from the underlying allocator failed.
+  AllocError,
+  /// Requested size does not fit in a valid allocation layout.
+  InvalidSize,
+  /// Serialized input was malformed.
+  InvalidData,
+}
+
+#[derive(Debug)]
+/// A dynamically sizable bitmap.
+pub struct Bitmap<'map, A = Global>
+where
+  A: Allocator,
+{
+  /// Allocator used for backing storage.
+  allocator: A,
+  /// Words holding the bitmap bits, least significant bit first.
+  map: &'map mut [usize],
+  /// Layout used for the allocation.
+  layout: Layout,
+  /// Number of words allocated, which may exceed what `len` needs.
+  fields: usize,
+  /// Number of bits in use; bits at and past this index are always zero.
+  len: usize,
+}
+
+impl<'map, A> Bitmap<'map, A>
+where
+  A: Allocator,
+{
+  /// Try to create a new bitmap in the given allocator.
+  pub fn try_new_in(allocator: A, size: usize) -> Result<Self, BitmapError> {
+    let fields = size.div_ceil(WORD_BITS);
+
+    let layout = Layout::array::<usize>(fields).map_err(|_| BitmapError::InvalidSize)?;
+    let ptr = allocator
+      .allocate_zeroed(layout)
+      .map_err(|_| BitmapError::AllocError)?;
+    let map = unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr() as *mut usize, fields) };
+    Ok(Bitmap {
+      allocator,
+      map,
+      layout,
+      fields,
+      len: size,
+    })
+  }
+
+  /// Create a new bitmap, panicking on failure.
+  pub fn new_in(allocator: A, size: usize) -> Self {
+    Self::try_new_in(allocator, size).expect("Failed to create Bitmap")
+  }
+}
+
+impl<'map> Bitmap<'map, Global> {
+  /// Create a new bitmap using the global allocator.
+  pub fn new(size: usize) -> Self {
+    Self::new_in(Global, size)
+  }
+}
+
+impl<'map, A> Bitmap<'map, A>
+where
+  A: Allocator,
+{
+  /// Number of bits the bitmap can hold without reallocating.
+  pub fn capacity(&self) -> usize {
+    self.fields * WORD_BITS
+  }
+
+  /// Try to resize the bitmap to a new bit count.
+  ///
+  /// New bits start cleared; bits past a smaller size are discarded. Growth
+  /// reserves capacity geometrically. Shrinking hands storage back to the
+  /// allocator once at most a quarter of the capacity is in use, keeping
+  /// twice the needed room so that alternating growth and shrinking stays
+  /// amortized; a failed shrink leaves the allocation as it was.
+  pub fn try_resize(&mut self, new_size: usize) -> Result<(), BitmapError> {
+    if new_size > self.len {
+      self.try_reserve(new_size - self.len)?;
+    } else if new_size < self.len {
+      let new_fields = new_size.div_ceil(WORD_BITS);
+      let end = self.len.div_ceil(WORD_BITS);
+      if !new_size.is_multiple_of(WORD_BITS) {
+        self.map[new_size / WORD_BITS] &= (1 << (new_size % WORD_BITS)) - 1;
+      }
+      for word in &mut self.map[new_fields..end] {
+        *word = 0;
+      }
+      if new_fields <= self.fields / 4 {
+        let _ = self.shrink_fields(new_fields * 2);
+      }
+    }
+    self.len = new_size;
+    Ok(())
+  }
+
+  /// Resize the bitmap, panicking on failure.
+  pub fn resize(&mut self, new_size: usize) {
+    self.try_resize(new_size).expect("Failed to resize Bitmap");
+  }
+
+  /// Try to make room for at least `additional` more bits, growing the
+  /// capacity geometrically.
+  pub fn try_reserve(&mut self, additional: usize) -> Result<(), BitmapError> {
+    let required = self.required_fields(additional)?;
+    if required > self.fields {
+      self.grow_fields(required.max(self.fields.saturating_mul(2)))?;
+    }
+    Ok(())
+  }
+
+  /// Reserve room for `additional` more bits, panicking on failure.
+  pub fn reserve(&mut self, additional: usize) {
+    self.try_reserve(additional).expect("Failed to grow Bitmap");
+  }
+
+  /// Try to make room for exactly `additional` more bits, rounded up to
+  /// whole words.
+  pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), BitmapError> {
+    let required = self.required_fields(additional)?;
+    if required > self.fields {
+      self.grow_fields(required)?;
+    }
+    Ok(())
+  }
+
+  /// Reserve room for exactly `additional` more bits, panicking on failure.
+  pub fn reserve_exact(&mut self, additional: usize) {
+    self.try_reserve_exact(additional).expect("Failed to grow Bitmap");
+  }
+
+  /// Try to shrink the capacity to at least `min_capacity` bits and the
+  /// length, returning the rest to the allocator.
+  pub fn try_shrink_to(&mut self, min_capacity: usize) -> Result<(), BitmapError> {
+    let target = min_capacity.max(self.len).div_ceil(WORD_BITS);
+    if target < self.fields {
+      self.shrink_fields(target)?;
+    }
+    Ok(())
+  }
+
+  /// Shrink the capacity to at least `min_capacity` bits, panicking on failure.
+  pub fn shrink_to(&mut self, min_capacity: usize) {
+    self.try_shrink_to(min_capacity).expect("Failed to shrink Bitmap");
+  }
+
+  /// Try to shrink the capacity to fit the length.
+  pub fn try_shrink_to_fit(&mut self) -> Result<(), BitmapError> {
+    self.try_shrink_to(0)
+  }
+
+  /// Shrink the capacity to fit the length, panicking on failure.
+  pub fn shrink_to_fit(&mut self) {
+    self.try_shrink_to_fit().expect("Failed to shrink Bitmap");
+  }
+
+  /// Try to append a bit, growing the storage geometrically when full.
+  pub fn try_push(&mut self, value: bool) -> Result<(), BitmapError> {
+    self.try_reserve(1)?;
+    let index = self.len;
+    self.len += 1;
+    if value {
+      self.map[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+    Ok(())
+  }
+
+  /// Append a bit, panicking on allocation failure.
+  pub fn push(&mut self, value: bool) {
+    self.try_push(value).expect("Failed to grow Bitmap");
+  }
+
+  /// Remove and return the last bit, or `None` if the bitmap is empty.
+  pub fn pop(&mut self) -> Option<bool> {
+    let index = self.len.checked_sub(1)?;
+    let value = self.get(index);
+    self.map[index / WORD_BITS] &= !(1 << (index % WORD_BITS));
+    self.len = index;
+    Some(value)
+  }
+
+  /// Try to build a [`RankIndex`] for constant-time rank queries.
+  ///
+  /// The index borrows the bitmap, so it cannot go stale.
+  pub fn try_rank_index(&self) -> Result<RankIndex<'_, A>, BitmapError> {
+    let words = &self.map[..self.len.div_ceil(WORD_BITS)];
+    let mut blocks = Vec::try_with_capacity_in(
+      words.len().div_ceil(SUPERBLOCK_WORDS) + 1,
+      &self.allocator,
+    )
+    .map_err(|_| BitmapError::AllocError)?;
+    let mut total = 0;
+    blocks.push(0);
+    for chunk in words.chunks(SUPERBLOCK_WORDS) {
+      total += chunk.iter().map(|word| word.count_ones() as usize).sum::<usize>();
+      blocks.push(total);
+    }
+    Ok(RankIndex {
+      words,
+      len: self.len,
+      blocks,
+    })
+  }
+
+  /// Build a [`RankIndex`], panicking on allocation failure.
+  pub fn rank_index(&self) -> RankIndex<'_, A> {
+    self.try_rank_index().expect("Failed to build RankIndex")
+  }
+
+  /// Words needed to hold `additional` bits past the length.
+  fn required_fields(&self, additional: usize) -> Result<usize, BitmapError> {
+    let bits = self
+      .len
+      .checked_add(additional)
+      .ok_or(BitmapError::InvalidSize)?;
+    Ok(bits.div_ceil(WORD_BITS))
+  }
+
+  /// Reallocate the storage to hold `new_fields` words, zeroing new ones.
+  fn grow_fields(&mut self, new_fields: usize) -> Result<(), BitmapError> {
+    let new_layout = Layout::array::<usize>(new_fields).map_err(|_| BitmapError::InvalidSize)?;
+    let old_ptr = NonNull::new(self.map.as_mut_ptr()).unwrap().cast();
+    let new_ptr = unsafe {
+      self
+        .allocator
+        .grow_zeroed(old_ptr, self.layout, new_layout)
+        .map_err(|_| BitmapError::AllocError)?
+    };
+    self.map =
+      unsafe { core::slice::from_raw_parts_mut(new_ptr.as_ptr() as *mut usize, new_fields) };
+    self.layout = new_layout;
+    self.fields = new_fields;
+    Ok(())
+  }
+
+  /// Reallocate the storage down to `new_fields` words.
+  ///
+  /// The dropped words must already be clear.
+  fn shrink_fields(&mut self, new_fields: usize) -> Result<(), BitmapError> {
+    debug_assert!(new_fields <= self.fields);
+    let new_layout = Layout::array::<usize>(new_fields).map_err(|_| BitmapError::InvalidSize)?;
+    let old_ptr = NonNull::new(self.map.as_mut_ptr()).unwrap().cast();
+    let new_ptr = unsafe {
+      self
+        .allocator
+        .shrink(old_ptr, self.layout, new_layout)
+        .map_err(|_| BitmapError::AllocError)?
+    };
+    self.map =
+      unsafe { core::slice::from_raw_parts_mut(new_ptr.as_ptr() as *mut usize, new_fields) };
+    self.layout = new_layout;
+    self.fields = new_fields;
+    Ok(())
+  }
+}
+
+impl<A> Storage for Bitmap<'_, A>
+where
+  A: Allocator,
+{
+  fn bit_len(&self) -> usize {
+    self.len
+  }
+
+  fn raw_words(&self) -> &[usize] {
+    &self.map[..self.len.div_ceil(WORD_BITS)]
+  }
+
+  fn raw_words_mut(&mut self, _: Token) -> &mut [usize] {
+    &mut self.map[..self.len.div_ceil(WORD_BITS)]
+  }
+}
+
+/// Inherent forms of the [`BitmapOps`] queries and updates, so callers do
+/// not need the trait in scope.
+impl<A> Bitmap<'_, A>
+where
+  A: Allocator,
+{
+  /// Number of bits in the bitmap.
+  pub fn len(&self) -> usize {
+    BitmapOps::len(self)
+  }
+
+  /// Whether the bitmap holds no bits.
+  pub fn is_empty(&self) -> bool {
+    BitmapOps::is_empty(self)
+  }
+
+  /// Try to get the bit at the given index.
+  pub fn try_get(&self, index: usize) -> Result<bool, BitmapError> {
+    BitmapOps::try_get(self, index)
+  }
+
+  /// Get the bit at the given index, panicking on out-of-bounds.
+  pub fn get(&self, index: usize) -> bool {
+    BitmapOps::get(self, index)
+  }
+
+  /// Try to set the bit at the given index.
+  pub fn try_set(&mut self, index: usize) -> Result<(), BitmapError> {
+    BitmapOps::try_set(self, index)
+  }
+
+  /// Set the bit at the given index, panicking on out-of-bounds.
+  pub fn set(&mut self, index: usize) {
+    BitmapOps::set(self, index)
+  }
+
+  /// Try to clear the bit at the given index.
+  pub fn try_clear(&mut self, index: usize) -> Result<(), BitmapError> {
+    BitmapOps::try_clear(self, index)
+  }
+
+  /// Clear the bit at the given index, panicking on out-of-bounds.
+  pub fn clear(&mut self, index: usize) {
+    BitmapOps::clear(self, index)
+  }
+
+  /// Try to flip the bit at the given index.
+  pub fn try_toggle(&mut self, index: usize) -> Result<(), BitmapError> {
+    BitmapOps::try_toggle(self, index)
+  }
+
+  /// Flip the bit at the given index, panicking on out-of-bounds.
+  pub fn toggle(&mut self, index: usize) {
+    BitmapOps::toggle(self, index)
+  }
+
+  /// Try to set every bit in `range`.
+  pub fn try_set_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    BitmapOps::try_set_range(self, range)
+  }
+
+  /// Set every bit in `range`, panicking on out-of-bounds.
+  pub fn set_range(&mut self, range: Range<usize>) {
+    BitmapOps::set_range(self, range)
+  }
+
+  /// Try to clear every bit in `range`.
+  pub fn try_clear_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    BitmapOps::try_clear_range(self, range)
+  }
+
+  /// Clear every bit in `range`, panicking on out-of-bounds.
+  pub fn clear_range(&mut self, range: Range<usize>) {
+    BitmapOps::clear_range(self, range)
+  }
+
+  /// Try to flip every bit in `range`.
+  pub fn try_toggle_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    BitmapOps::try_toggle_range(self, range)
+  }
+
+  /// Flip every bit in `range`, panicking on out-of-bounds.
+  pub fn toggle_range(&mut self, range: Range<usize>) {
+    BitmapOps::toggle_range(self, range)
+  }
+
+  /// Index of the lowest set bit.
+  pub fn first_set(&self) -> Option<usize> {
+    BitmapOps::first_set(self)
+  }
+
+  /// Index of the lowest clear bit.
+  pub fn first_clear(&self) -> Option<usize> {
+    BitmapOps::first_clear(self)
+  }
+
+  /// Index of the lowest set bit at or after `from`.
+  pub fn next_set(&self, from: usize) -> Option<usize> {
+    BitmapOps::next_set(self, from)
+  }
+
+  /// Index of the lowest clear bit at or after `from`.
+  pub fn next_clear(&self, from: usize) -> Option<usize> {
+    BitmapOps::next_clear(self, from)
+  }
+
+  /// Index of the highest set bit.
+  pub fn last_set(&self) -> Option<usize> {
+    BitmapOps::last_set(self)
+  }
+
+  /// Iterate over the indices of set bits in ascending order.
+  pub fn iter_ones(&self) -> Ones<'_> {
+    BitmapOps::iter_ones(self)
+  }
+
+  /// Iterate over the indices of clear bits in ascending order.
+  pub fn iter_zeros(&self) -> Zeros<'_> {
+    BitmapOps::iter_zeros(self)
+  }
+
+  /// Number of set bits.
+  pub fn count_ones(&self) -> usize {
+    BitmapOps::count_ones(self)
+  }
+
+  /// Number of clear bits.
+  pub fn count_zeros(&self) -> usize {
+    BitmapOps::count_zeros(self)
+  }
+
+  /// Number of set bits strictly before `index`, panicking if `index > len`.
+  pub fn rank(&self, index: usize) -> usize {
+    BitmapOps::rank(self, index)
+  }
+
+  /// Index of the set bit with `rank` set bits before it.
+  pub fn select(&self, rank: usize) -> Option<usize> {
+    BitmapOps::select(self, rank)
+  }
+}
+
+/// Set algebra.
+///
+/// Operands of different lengths are treated as if the shorter one were
+/// padded with clear bits, and results take the longer length. In-place
+/// operations therefore grow `self` when `other` is longer, panicking if
+/// that allocation fails.
+impl<'map, A> Bitmap<'map, A>
+where
+  A: Allocator,
+{
+  /// Words holding bits below `len`.
+  fn words(&self) -> &[usize] {
+    &self.map[..self.len.div_ceil(WORD_BITS)]
+  }
+
+  /// Replace each word with `op(word, other_word)`.
+  fn combine_with<B>(&mut self, other: &Bitmap<'_, B>, op: impl Fn(usize, usize) -> usize)
+  where
+    B: Allocator,
+  {
+    if other.len > self.len {
+      self.resize(other.len);
+    }
+    let words = self.len.div_ceil(WORD_BITS);
+    let rhs = other.words();
+    for (index, word) in self.map[..words].iter_mut().enumerate() {
+      *word = op(*word, rhs.get(index).copied().unwrap_or(0));
+    }
+  }
+
+  /// Set every bit that is set in `other`.
+  pub fn union_with<B>(&mut self, other: &Bitmap<'_, B>)
+  where
+    B: Allocator,
+  {
+    self.combine_with(other, |lhs, rhs| lhs | rhs);
+  }
+
+  /// Clear every bit that is not set in `other`.
+  pub fn intersect_with<B>(&mut self, other: &Bitmap<'_, B>)
+  where
+    B: Allocator,
+  {
+    self.combine_with(other, |lhs, rhs| lhs & rhs);
+  }
+
+  /// Clear every bit that is set in `other`.
+  pub fn difference_with<B>(&mut self, other: &Bitmap<'_, B>)
+  where
+    B: Allocator,
+  {
+    self.combine_with(other, |lhs, rhs| lhs & !rhs);
+  }
+
+  /// Flip every bit that is set in `other`.
+  pub fn symmetric_difference_with<B>(&mut self, other: &Bitmap<'_, B>)
+  where
+    B: Allocator,
+  {
+    self.combine_with(other, |lhs, rhs| lhs ^ rhs);
+  }
+
+  /// Flip every bit.
+  pub fn invert(&mut self) {
+    self.toggle_range(0..self.len);
+  }
+
+  /// Whether every bit set in `self` is also set in `other`.
+  pub fn is_subset<B>(&self, other: &Bitmap<'_, B>) -> bool
+  where
+    B: Allocator,
+  {
+    let rhs = other.words();
+    self
+      .words()
+      .iter()
+      .enumerate()
+      .all(|(index, &word)| word & !rhs.get(index).copied().unwrap_or(0) == 0)
+  }
+
+  /// Whether every bit set in `other` is also set in `self`.
+  pub fn is_superset<B>(&self, other: &Bitmap<'_, B>) -> bool
+  where
+    B: Allocator,
+  {
+    other.is_subset(self)
+  }
+
+  /// Whether `self` and `other` have no set bit in common.
+  pub fn is_disjoint<B>(&self, other: &Bitmap<'_, B>) -> bool
+  where
+    B: Allocator,
+  {
+    self
+      .words()
+      .iter()
+      .zip(other.words())
+      .all(|(lhs, rhs)| lhs & rhs == 0)
+  }
+}
+
+impl<'map, A> Bitmap<'map, A>
+where
+  A: Allocator + Clone,
+{
+  /// Try to copy the bitmap into a new allocation sized to its length.
+  pub fn try_clone(&self) -> Result<Self, BitmapError> {
+    let copy = Self::try_new_in(self.allocator.clone(), self.len)?;
+    copy.map.copy_from_slice(self.words());
+    Ok(copy)
+  }
+
+  /// Bits set in either bitmap.
+  pub fn union<B>(&self, other: &Bitmap<'_, B>) -> Self
+  where
+    B: Allocator,
+  {
+    let mut result = self.clone();
+    result.union_with(other);
+    result
+  }
+
+  /// Bits set in both bitmaps.
+  pub fn intersection<B>(&self, other: &Bitmap<'_, B>) -> Self
+  where
+    B: Allocator,
+  {
+    let mut result = self.clone();
+    result.intersect_with(other);
+    result
+  }
+
+  /// Bits set in `self` but not in `other`.
+  pub fn difference<B>(&self, other: &Bitmap<'_, B>) -> Self
+  where
+    B: Allocator,
+  {
+    let mut result = self.clone();
+    result.difference_with(other);
+    result
+  }
+
+  /// Bits set in exactly one of the bitmaps.
+  pub fn symmetric_difference<B>(&self, other: &Bitmap<'_, B>) -> Self
+  where
+    B: Allocator,
+  {
+    let mut result = self.clone();
+    result.symmetric_difference_with(other);
+    result
+  }
+
+  /// Bits clear in `self`.
+  pub fn complement(&self) -> Self {
+    let mut result = self.clone();
+    result.invert();
+    result
+  }
+}
+
+impl<A> Clone for Bitmap<'_, A>
+where
+  A: Allocator + Clone,
+{
+  fn clone(&self) -> Self {
+    self.try_clone().expect("Failed to clone Bitmap")
+  }
+}
+
+impl<A, B> PartialEq<Bitmap<'_, B>> for Bitmap<'_, A>
+where
+  A: Allocator,
+  B: Allocator,
+{
+  fn eq(&self, other: &Bitmap<'_, B>) -> bool {
+    self.len == other.len && self.words() == other.words()
+  }
+}
+
+impl<A> Eq for Bitmap<'_, A> where A: Allocator {}
+
+impl<A> Hash for Bitmap<'_, A>
+where
+  A: Allocator,
+{
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.len.hash(state);
+    self.words().hash(state);
+  }
+}
+
+macro_rules! bitmap_operator {
+  ($op:ident, $method:ident, $assign:ident, $assign_method:ident, $with:ident) => {
+    impl<A, B> $assign<&Bitmap<'_, B>> for Bitmap<'_, A>
+    where
+      A: Allocator,
+      B: Allocator,
+    {
+      fn $assign_method(&mut self, rhs: &Bitmap<'_, B>) {
+        self.$with(rhs);
+      }
+    }
+
+    impl<'map, A, B> $op<&Bitmap<'_, B>> for Bitmap<'map, A>
+    where
+      A: Allocator,
+      B: Allocator,
+    {
+      type Output = Bitmap<'map, A>;
+
+      fn $method(mut self, rhs: &Bitmap<'_, B>) -> Self::Output {
+        self.$with(rhs);
+        self
+      }
+    }
+
+    impl<'map, A, B> $op<&Bitmap<'_, B>> for &Bitmap<'map, A>
+    where
+      A: Allocator + Clone,
+      B: Allocator,
+    {
+      type Output = Bitmap<'map, A>;
+
+      fn $method(self, rhs: &Bitmap<'_, B>) -> Self::Output {
+        let mut result = self.clone();
+        result.$with(rhs);
+        result
+      }
+    }
+  };
+}
+
+bitmap_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, intersect_with);
+bitmap_operator!(BitOr, bitor, BitOrAssign, bitor_assign, union_with);
+bitmap_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, symmetric_difference_with);
+
+impl<A> Not for Bitmap<'_, A>
+where
+  A: Allocator,
+{
+  type Output = Self;
+
+  fn not(mut self) -> Self {
+    self.invert();
+    self
+  }
+}
+
+impl<'map, A> Not for &Bitmap<'map, A>
+where
+  A: Allocator + Clone,
+{
+  type Output = Bitmap<'map, A>;
+
+  fn not(self) -> Self::Output {
+    self.complement()
+  }
+}
+
+/// Cumulative popcounts over superblocks of a [`Bitmap`].
+///
+/// Answers [`rank`](Self::rank) with one table lookup plus at most
+/// eight word popcounts, and narrows [`select`](Self::select) with a
+/// binary search over superblocks.
+#[derive(Debug)]
+pub struct RankIndex<'a, A = Global>
+where
+  A: Allocator,
+{
+  /// Words of the indexed bitmap.
+  words: &'a [usize],
+  /// Length of the indexed bitmap in bits.
+  len: usize,
+  /// `blocks[i]` is the number of set bits before superblock `i`.
+  blocks: Vec<usize, &'a A>,
+}
+
+impl<A> RankIndex<'_, A>
+where
+  A: Allocator,
+{
+  /// Number of set bits strictly before `index`, panicking if `index > len`.
+  pub fn rank(&self, index: usize) -> usize {
+    assert!(index <= self.len, "Bitmap index out of bounds");
+    let block = index / WORD_BITS / SUPERBLOCK_WORDS;
+    self.blocks[block] + count_between(self.words, block * SUPERBLOCK_WORDS * WORD_BITS, index)
+  }
+
+  /// Index of the set bit with `rank` set bits before it.
+  pub fn select(&self, rank: usize) -> Option<usize> {
+    let block = self.blocks.partition_point(|&ones| ones <= rank).checked_sub(1)?;
+    select_from(
+      self.words,
+      block * SUPERBLOCK_WORDS,
+      self.len,
+      rank - self.blocks[block],
+    )
+  }
+
+  /// Total number of set bits.
+  pub fn count_ones(&self) -> usize {
+    self.blocks[self.blocks.len() - 1]
+  }
+}
+
+impl<'map, A> Drop for Bitmap<'map, A>
+where
+  A: Allocator,
+{
+  fn drop(&mut self) {
+    let layout = self.layout;
+    unsafe {
+      self.allocator.deallocate(
+        NonNull::new(self.map.as_mut_ptr()).unwrap().cast(),
+        layout,
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_bitmap() {
+    let mut bitmap = Bitmap::new(64);
+    assert!(bitmap.try_set(10).is_ok());
+    assert!(bitmap.try_get(10).unwrap());
+    assert!(bitmap.try_clear(10).is_ok());
+    assert!(!bitmap.try_get(10).unwrap());
+  }
+
+  #[test]
+  fn test_resize() {
+    let mut bitmap = Bitmap::new(64);
+    assert!(bitmap.try_set(10).is_ok());
+    assert!(bitmap.try_resize(128).is_ok());
+    assert!(bitmap.try_get(10).unwrap());
+  }
+
+  #[test]
+  fn test_arbitrary_length() {
+    let mut bitmap = Bitmap::new(13);
+    assert_eq!(bitmap.len(), 13);
+    bitmap.set(12);
+    assert!(bitmap.try_set(13).is_err());
+    assert!(bitmap.try_get(13).is_err());
+
+    bitmap.set(9);
+    bitmap.resize(10);
+    assert!(bitmap.get(9));
+    bitmap.resize(13);
+    assert!(!bitmap.get(12));
+
+    bitmap.resize(3);
+    bitmap.resize(20);
+    assert!(!bitmap.get(9));
+  }
+
+  #[test]
+  fn test_push_pop() {
+    let mut bitmap = Bitmap::new(0);
+    assert!(bitmap.is_empty());
+    assert_eq!(bitmap.pop(), None);
+
+    for i in 0..100 {
+      bitmap.push(i % 3 == 0);
+    }
+    assert_eq!(bitmap.len(), 100);
+    assert!(bitmap.get(99));
+    assert!(!bitmap.get(98));
+
+    assert_eq!(bitmap.pop(), Some(true));
+    assert_eq!(bitmap.pop(), Some(false));
+    bitmap.resize(100);
+    assert!(!bitmap.get(99));
+    assert!(!bitmap.get(98));
+  }
+
+  #[test]
+  fn test_search() {
+    let mut bitmap = Bitmap::new(200);
+    assert_eq!(bitmap.first_set(), None);
+    assert_eq!(bitmap.first_clear(), Some(0));
+    assert_eq!(bitmap.last_set(), None);
+
+    bitmap.set(3);
+    bitmap.set(64);
+    bitmap.set(150);
+    assert_eq!(bitmap.first_set(), Some(3));
+    assert_eq!(bitmap.next_set(4), Some(64));
+    assert_eq!(bitmap.next_set(65), Some(150));
+    assert_eq!(bitmap.next_set(151), None);
+    assert_eq!(bitmap.next_set(500), None);
+    assert_eq!(bitmap.last_set(), Some(150));
+
+    bitmap.set_range(0..3);
+    assert_eq!(bitmap.first_clear(), Some(4));
+    assert_eq!(bitmap.next_clear(64), Some(65));
+
+    bitmap.set_range(0..200);
+    assert_eq!(bitmap.first_clear(), None);
+    assert_eq!(bitmap.last_set(), Some(199));
+  }
+
+  #[test]
+  fn test_ranges() {
+    let mut bitmap = Bitmap::new(130);
+    bitmap.set_range(60..70);
+    assert!((60..70).all(|i| bitmap.get(i)));
+    assert!(!bitmap.get(59) && !bitmap.get(70));
+
+    bitmap.clear_range(62..66);
+    assert_eq!(bitmap.next_clear(60), Some(62));
+    assert_eq!(bitmap.next_set(62), Some(66));
+
+    bitmap.toggle_range(0..130);
+    assert!(!bitmap.get(60));
+    assert!(bitmap.get(63));
+    assert!(bitmap.get(129));
+    bitmap.toggle(129);
+    assert_eq!(bitmap.last_set(), Some(128));
+
+    assert!(bitmap.try_set_range(120..131).is_err());
+    bitmap.set_range(5..5);
+    bitmap.resize(200);
+    assert_eq!(bitmap.next_set(130), None);
+  }
+
+  #[test]
+  fn test_iter() {
+    let mut bitmap = Bitmap::new(140);
+    for i in [0, 5, 63, 64, 100, 139] {
+      bitmap.set(i);
+    }
+    let ones: Vec<usize> = bitmap.iter_ones().collect();
+    assert_eq!(ones, [0, 5, 63, 64, 100, 139]);
+    let back: Vec<usize> = bitmap.iter_ones().rev().collect();
+    assert_eq!(back, [139, 100, 64, 63, 5, 0]);
+
+    let mut iter = bitmap.iter_ones();
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(139));
+    assert_eq!(iter.next_back(), Some(100));
+    assert_eq!(iter.next(), Some(5));
+    assert_eq!(iter.next(), Some(63));
+    assert_eq!(iter.next_back(), Some(64));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(bitmap.iter_zeros().count(), 134);
+    assert_eq!(bitmap.iter_zeros().next_back(), Some(138));
+    assert!(bitmap.iter_zeros().all(|i| !bitmap.get(i)));
+    assert_eq!(Bitmap::new(0).iter_ones().next(), None);
+  }
+
+  #[test]
+  fn test_rank_select() {
+    let mut bitmap = Bitmap::new(2000);
+    let ones: Vec<usize> = (0..2000).filter(|i| i % 7 == 3 || i % 64 == 0).collect();
+    for &i in &ones {
+      bitmap.set(i);
+    }
+    assert_eq!(bitmap.count_ones(), ones.len());
+    assert_eq!(bitmap.count_zeros(), 2000 - ones.len());
+
+    let index = bitmap.rank_index();
+    assert_eq!(index.count_ones(), ones.len());
+    for i in 0..=2000 {
+      let expected = ones.iter().filter(|&&one| one < i).count();
+      assert_eq!(bitmap.rank(i), expected);
+      assert_eq!(index.rank(i), expected);
+    }
+    for (k, &one) in ones.iter().enumerate() {
+      assert_eq!(bitmap.select(k), Some(one));
+      assert_eq!(index.select(k), Some(one));
+    }
+    assert_eq!(bitmap.select(ones.len()), None);
+    assert_eq!(index.select(ones.len()), None);
+  }
+
+  #[test]
+  fn test_set_algebra() {
+    let mut live = Bitmap::new(100);
+    live.set_range(10..70);
+    let mut killed = Bitmap::new(100);
+    killed.set_range(60..90);
+
+    assert_eq!(live.union(&killed).iter_ones().collect::<Vec<_>>(), (10..90).collect::<Vec<_>>());
+    assert_eq!(&live & &killed, {
+      let mut expected = Bitmap::new(100);
+      expected.set_range(60..70);
+      expected
+    });
+    assert_eq!(live.difference(&killed).last_set(), Some(59));
+    assert_eq!((&live ^ &killed).count_ones(), 50 + 20);
+    assert_eq!((!&live).count_ones(), 40);
+    assert_eq!(live.complement(), !live.clone());
+
+    let mut acc = live.clone();
+    acc |= &killed;
+    acc &= &killed;
+    assert_eq!(acc, killed);
+    acc ^= &killed;
+    assert_eq!(acc.first_set(), None);
+
+    assert!(acc.is_subset(&live));
+    assert!(live.intersection(&killed).is_subset(&live));
+    assert!(!live.is_subset(&killed));
+    assert!(live.is_superset(&live.intersection(&killed)));
+    assert!(!live.is_disjoint(&killed));
+    assert!(live.difference(&killed).is_disjoint(&killed));
+  }
+
+  #[test]
+  fn test_mixed_lengths() {
+    let mut short = Bitmap::new(10);
+    short.set(3);
+    let mut long = Bitmap::new(200);
+    long.set(150);
+
+    let union = &short | &long;
+    assert_eq!(union.len(), 200);
+    assert_eq!(union.iter_ones().collect::<Vec<_>>(), [3, 150]);
+    assert!(short.is_disjoint(&long));
+    assert!(!long.is_subset(&short));
+
+    short &= &long;
+    assert_eq!(short.len(), 200);
+    assert_eq!(short.first_set(), None);
+  }
+
+  #[test]
+  fn test_eq_hash_ignore_capacity() {
+    use core::hash::BuildHasher;
+    use std::collections::hash_map::RandomState;
+
+    let mut grown = Bitmap::new(0);
+    for i in 0..70 {
+      grown.push(i % 2 == 0);
+    }
+    let mut exact = Bitmap::new(70);
+    for i in (0..70).step_by(2) {
+      exact.set(i);
+    }
+    assert_eq!(grown, exact);
+
+    let state = RandomState::new();
+    assert_eq!(state.hash_one(&grown), state.hash_one(&exact));
+    exact.push(false);
+    assert_ne!(grown, exact);
+  }
+
+  #[test]
+  fn test_runs_across_words() {
+    let mut bitmap = Bitmap::new(300);
+    bitmap.set_range(0..60);
+    bitmap.set(70);
+    bitmap.set_range(150..200);
+
+    assert_eq!(bitmap.find_clear_run(5, 1), Some(60));
+    assert_eq!(bitmap.find_clear_run(20, 1), Some(71));
+    assert_eq!(bitmap.find_clear_run(79, 1), Some(71));
+    assert_eq!(bitmap.find_clear_run(80, 1), Some(200));
+    assert_eq!(bitmap.find_clear_run(20, 32), Some(96));
+    assert_eq!(bitmap.find_clear_run(100, 1), Some(200));
+    assert_eq!(bitmap.find_clear_run(101, 1), None);
+    assert_eq!(bitmap.find_clear_run(0, 64), Some(0));
+
+    assert_eq!(bitmap.find_set_run(60), Some(0));
+    assert_eq!(bitmap.find_set_run(61), None);
+    assert_eq!(bitmap.find_set_run(1), Some(0));
+
+    assert_eq!(bitmap.claim_run(40, 64), Some(256..296));
+    assert_eq!(bitmap.claim_run(40, 8), Some(72..112));
+    assert_eq!(bitmap.claim_run(38, 1), Some(112..150));
+    assert_eq!(bitmap.find_set_run(128), Some(72));
+    assert_eq!(bitmap.claim_run(60, 1), None);
+    assert_eq!(bitmap.claim_run(56, 1), Some(200..256));
+    assert_eq!(bitmap.first_clear(), Some(60));
+  }
+
+  #[test]
+  fn test_capacity_policy() {
+    let mut bitmap = Bitmap::new(0);
+    assert_eq!(bitmap.capacity(), 0);
+    let mut growths = 0;
+    for _ in 0..100 * WORD_BITS {
+      let capacity = bitmap.capacity();
+      bitmap.push(true);
+      if bitmap.capacity() != capacity {
+        growths += 1;
+      }
+    }
+    assert_eq!(bitmap.capacity(), 128 * WORD_BITS);
+    assert_eq!(growths, 8);
+
+    bitmap.resize(101 * WORD_BITS);
+    assert_eq!(bitmap.capacity(), 128 * WORD_BITS);
+    bitmap.resize(129 * WORD_BITS);
+    assert_eq!(bitmap.capacity(), 256 * WORD_BITS);
+    bitmap.reserve_exact(200 * WORD_BITS);
+    assert_eq!(bitmap.capacity(), 329 * WORD_BITS);
+
+    bitmap.resize(50 * WORD_BITS);
+    assert_eq!(bitmap.capacity(), 100 * WORD_BITS);
+    assert_eq!(bitmap.count_ones(), 50 * WORD_BITS);
+    bitmap.resize(40 * WORD_BITS + 1);
+    assert_eq!(bitmap.capacity(), 100 * WORD_BITS);
+    bitmap.shrink_to(60 * WORD_BITS);
+    assert_eq!(bitmap.capacity(), 60 * WORD_BITS);
+    bitmap.shrink_to_fit();
+    assert_eq!(bitmap.capacity(), 41 * WORD_BITS);
+    assert_eq!(bitmap.last_set(), Some(40 * WORD_BITS));
+  }
+
+  #[test]
+  fn test_shrink_returns_memory() {
+    use crate::fixed::FixedAllocator;
+
+    let mut buffer = [0u8; 1024];
+    let fixed = FixedAllocator::new(&mut buffer);
+    let word = core::mem::size_of::<usize>();
+
+    let mut bitmap = Bitmap::new_in(&fixed, 64 * WORD_BITS);
+    assert_eq!(fixed.used(), 64 * word);
+    bitmap.set(WORD_BITS);
+    bitmap.resize(2 * WORD_BITS);
+    assert_eq!(fixed.used(), 4 * word);
+    bitmap.shrink_to_fit();
+    assert_eq!(fixed.used(), 2 * word);
+    assert!(bitmap.get(WORD_BITS));
+
+    bitmap.reserve(1);
+    assert_eq!(bitmap.capacity(), 4 * WORD_BITS);
+  }
+
+  #[test]
+  fn test_index_overflow() {
+    fn check(bits: &mut impl BitmapOps) {
+      let out_of_bounds = |result| matches!(result, Err(BitmapError::OutOfBounds));
+      assert!(out_of_bounds(bits.try_set(usize::MAX)));
+      assert!(out_of_bounds(bits.try_clear(usize::MAX)));
+      assert!(out_of_bounds(bits.try_toggle(usize::MAX)));
+      assert!(bits.try_get(usize::MAX).is_err());
+      assert_eq!(bits.count_ones(), 0);
+    }
+
+    let mut words = [0; 1];
+    check(&mut Bitmap::new(3));
+    check(&mut BitmapRef::with_len(&mut words, 3));
+    check(&mut InlineBitmap::<3>::new());
+  }
+}
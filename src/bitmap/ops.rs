@@ -0,0 +1,419 @@
+//! Query and mutation API shared by the dense bitmap types.
+
+use core::{
+  iter::FusedIterator,
+  ops::Range,
+};
+
+use super::{
+  BitmapError,
+  WORD_BITS,
+};
+
+mod private {
+  /// Argument only this crate can construct.
+  #[derive(Debug, Clone, Copy)]
+  pub struct Token;
+}
+
+pub(crate) use private::Token;
+
+/// Raw word access backing [`BitmapOps`].
+///
+/// Mutable access takes a [`Token`] that cannot be named outside this crate,
+/// so only this crate can implement [`BitmapOps`] or write the words, and no
+/// caller can break the trailing-bit invariant.
+pub trait Storage {
+  /// Number of bits in the bitmap.
+  fn bit_len(&self) -> usize;
+
+  /// The `bit_len().div_ceil(WORD_BITS)` words holding the bits.
+  fn raw_words(&self) -> &[usize];
+
+  /// Mutable access to the words returned by [`raw_words`](Self::raw_words).
+  ///
+  /// Bits at and past `bit_len()` must be left clear.
+  fn raw_words_mut(&mut self, _: Token) -> &mut [usize];
+}
+
+/// Bit queries and updates common to [`Bitmap`](super::Bitmap),
+/// [`BitmapRef`](super::BitmapRef) and [`InlineBitmap`](super::InlineBitmap).
+pub trait BitmapOps: Storage {
+  /// Number of bits in the bitmap.
+  fn len(&self) -> usize {
+    self.bit_len()
+  }
+
+  /// Whether the bitmap holds no bits.
+  fn is_empty(&self) -> bool {
+    self.bit_len() == 0
+  }
+
+  /// Try to get the bit at the given index.
+  fn try_get(&self, index: usize) -> Result<bool, BitmapError> {
+    if index >= self.bit_len() {
+      return Err(BitmapError::OutOfBounds);
+    }
+    Ok(self.raw_words()[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0)
+  }
+
+  /// Get the bit at the given index, panicking on out-of-bounds.
+  fn get(&self, index: usize) -> bool {
+    self.try_get(index).expect("Bitmap index out of bounds")
+  }
+
+  /// Try to set the bit at the given index.
+  fn try_set(&mut self, index: usize) -> Result<(), BitmapError> {
+    if index >= self.bit_len() {
+      return Err(BitmapError::OutOfBounds);
+    }
+    self.try_set_range(index..index + 1)
+  }
+
+  /// Set the bit at the given index, panicking on out-of-bounds.
+  fn set(&mut self, index: usize) {
+    self.try_set(index).expect("Bitmap index out of bounds");
+  }
+
+  /// Try to clear the bit at the given index.
+  fn try_clear(&mut self, index: usize) -> Result<(), BitmapError> {
+    if index >= self.bit_len() {
+      return Err(BitmapError::OutOfBounds);
+    }
+    self.try_clear_range(index..index + 1)
+  }
+
+  /// Clear the bit at the given index, panicking on out-of-bounds.
+  fn clear(&mut self, index: usize) {
+    self.try_clear(index).expect("Bitmap index out of bounds");
+  }
+
+  /// Try to flip the bit at the given index.
+  fn try_toggle(&mut self, index: usize) -> Result<(), BitmapError> {
+    if index >= self.bit_len() {
+      return Err(BitmapError::OutOfBounds);
+    }
+    self.try_toggle_range(index..index + 1)
+  }
+
+  /// Flip the bit at the given index, panicking on out-of-bounds.
+  fn toggle(&mut self, index: usize) {
+    self.try_toggle(index).expect("Bitmap index out of bounds");
+  }
+
+  /// Try to set every bit in `range`.
+  fn try_set_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    update_range(self, range, |word, mask| word | mask)
+  }
+
+  /// Set every bit in `range`, panicking on out-of-bounds.
+  fn set_range(&mut self, range: Range<usize>) {
+    self.try_set_range(range).expect("Bitmap range out of bounds");
+  }
+
+  /// Try to clear every bit in `range`.
+  fn try_clear_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    update_range(self, range, |word, mask| word & !mask)
+  }
+
+  /// Clear every bit in `range`, panicking on out-of-bounds.
+  fn clear_range(&mut self, range: Range<usize>) {
+    self.try_clear_range(range).expect("Bitmap range out of bounds");
+  }
+
+  /// Try to flip every bit in `range`.
+  fn try_toggle_range(&mut self, range: Range<usize>) -> Result<(), BitmapError> {
+    update_range(self, range, |word, mask| word ^ mask)
+  }
+
+  /// Flip every bit in `range`, panicking on out-of-bounds.
+  fn toggle_range(&mut self, range: Range<usize>) {
+    self.try_toggle_range(range).expect("Bitmap range out of bounds");
+  }
+
+  /// Index of the lowest set bit.
+  fn first_set(&self) -> Option<usize> {
+    self.next_set(0)
+  }
+
+  /// Index of the lowest clear bit.
+  fn first_clear(&self) -> Option<usize> {
+    self.next_clear(0)
+  }
+
+  /// Index of the lowest set bit at or after `from`.
+  fn next_set(&self, from: usize) -> Option<usize> {
+    find_next(self.raw_words(), from, self.bit_len(), |word| word)
+  }
+
+  /// Index of the lowest clear bit at or after `from`.
+  fn next_clear(&self, from: usize) -> Option<usize> {
+    find_next(self.raw_words(), from, self.bit_len(), |word| !word)
+  }
+
+  /// Index of the highest set bit.
+  fn last_set(&self) -> Option<usize> {
+    find_prev(self.raw_words(), 0, self.bit_len(), |word| word)
+  }
+
+  /// Iterate over the indices of set bits in ascending order.
+  fn iter_ones(&self) -> Ones<'_> {
+    Ones {
+      words: self.raw_words(),
+      front: 0,
+      back: self.bit_len(),
+    }
+  }
+
+  /// Iterate over the indices of clear bits in ascending order.
+  fn iter_zeros(&self) -> Zeros<'_> {
+    Zeros {
+      words: self.raw_words(),
+      front: 0,
+      back: self.bit_len(),
+    }
+  }
+
+  /// Number of set bits.
+  fn count_ones(&self) -> usize {
+    self
+      .raw_words()
+      .iter()
+      .map(|word| word.count_ones() as usize)
+      .sum()
+  }
+
+  /// Number of clear bits.
+  fn count_zeros(&self) -> usize {
+    self.bit_len() - self.count_ones()
+  }
+
+  /// Number of set bits strictly before `index`, panicking if `index > len`.
+  fn rank(&self, index: usize) -> usize {
+    assert!(index <= self.bit_len(), "Bitmap index out of bounds");
+    count_between(self.raw_words(), 0, index)
+  }
+
+  /// Index of the set bit with `rank` set bits before it.
+  fn select(&self, rank: usize) -> Option<usize> {
+    select_from(self.raw_words(), 0, self.bit_len(), rank)
+  }
+
+  /// Start of the lowest run of `n` clear bits beginning at a multiple of
+  /// `align`, panicking if `align` is zero.
+  fn find_clear_run(&self, n: usize, align: usize) -> Option<usize> {
+    find_run(self.raw_words(), self.bit_len(), n, align, |word| !word)
+  }
+
+  /// Start of the lowest run of `n` set bits.
+  fn find_set_run(&self, n: usize) -> Option<usize> {
+    find_run(self.raw_words(), self.bit_len(), n, 1, |word| word)
+  }
+
+  /// Find a run as [`find_clear_run`](Self::find_clear_run) does and set it.
+  fn claim_run(&mut self, n: usize, align: usize) -> Option<Range<usize>> {
+    let start = self.find_clear_run(n, align)?;
+    self.set_range(start..start + n);
+    Some(start..start + n)
+  }
+}
+
+impl<T> BitmapOps for T where T: Storage + ?Sized {}
+
+/// Start of the lowest `align`-aligned run of `n` bits set in `view(word)`.
+///
+/// Each probe skips past the first mismatching bit, using word scans on
+/// both the run start and its interior.
+fn find_run(
+  words: &[usize],
+  len: usize,
+  n: usize,
+  align: usize,
+  view: impl Fn(usize) -> usize,
+) -> Option<usize> {
+  assert!(align != 0, "run alignment must be non-zero");
+  if n == 0 {
+    return Some(0);
+  }
+  let mut from = 0;
+  loop {
+    let start = find_next(words, from, len, &view)?.checked_next_multiple_of(align)?;
+    let end = start.checked_add(n).filter(|&end| end <= len)?;
+    match find_next(words, start, end, |word| !view(word)) {
+      Some(mismatch) => from = mismatch + 1,
+      None => return Some(start),
+    }
+  }
+}
+
+/// Apply `op(word, mask)` to every word overlapping `range`.
+fn update_range<T>(
+  bits: &mut T,
+  range: Range<usize>,
+  op: impl Fn(usize, usize) -> usize,
+) -> Result<(), BitmapError>
+where
+  T: Storage + ?Sized,
+{
+  if range.start > range.end || range.end > bits.bit_len() {
+    return Err(BitmapError::OutOfBounds);
+  }
+  let words = bits.raw_words_mut(Token);
+  let mut start = range.start;
+  while start < range.end {
+    let index = start / WORD_BITS;
+    let bit = start % WORD_BITS;
+    let span = (WORD_BITS - bit).min(range.end - start);
+    let mask = (usize::MAX >> (WORD_BITS - span)) << bit;
+    words[index] = op(words[index], mask);
+    start += span;
+  }
+  Ok(())
+}
+
+/// Lowest bit in `from..end` that is set in `view(word)`.
+pub(super) fn find_next(
+  words: &[usize],
+  from: usize,
+  end: usize,
+  view: impl Fn(usize) -> usize,
+) -> Option<usize> {
+  if from >= end {
+    return None;
+  }
+  let last = (end - 1) / WORD_BITS;
+  let mut index = from / WORD_BITS;
+  let mut bits = view(words[index]) & (usize::MAX << (from % WORD_BITS));
+  loop {
+    if bits != 0 {
+      let found = index * WORD_BITS + bits.trailing_zeros() as usize;
+      return (found < end).then_some(found);
+    }
+    if index == last {
+      return None;
+    }
+    index += 1;
+    bits = view(words[index]);
+  }
+}
+
+/// Highest bit in `start..end` that is set in `view(word)`.
+pub(super) fn find_prev(
+  words: &[usize],
+  start: usize,
+  end: usize,
+  view: impl Fn(usize) -> usize,
+) -> Option<usize> {
+  if start >= end {
+    return None;
+  }
+  let first = start / WORD_BITS;
+  let mut index = (end - 1) / WORD_BITS;
+  let mut bits = view(words[index]) & (usize::MAX >> (WORD_BITS - 1 - (end - 1) % WORD_BITS));
+  loop {
+    if bits != 0 {
+      let found = index * WORD_BITS + (WORD_BITS - 1 - bits.leading_zeros() as usize);
+      return (found >= start).then_some(found);
+    }
+    if index == first {
+      return None;
+    }
+    index -= 1;
+    bits = view(words[index]);
+  }
+}
+
+/// Number of set bits in `start..end`, where `start` is word aligned.
+pub(super) fn count_between(words: &[usize], start: usize, end: usize) -> usize {
+  let full = end / WORD_BITS;
+  let mut count: usize = words[start / WORD_BITS..full]
+    .iter()
+    .map(|word| word.count_ones() as usize)
+    .sum();
+  if !end.is_multiple_of(WORD_BITS) {
+    count += (words[full] & ((1 << (end % WORD_BITS)) - 1)).count_ones() as usize;
+  }
+  count
+}
+
+/// Index of the set bit preceded by `rank` set bits, scanning from word `first`.
+pub(super) fn select_from(words: &[usize], first: usize, len: usize, mut rank: usize) -> Option<usize> {
+  for (index, &word) in words[..len.div_ceil(WORD_BITS)]
+    .iter()
+    .enumerate()
+    .skip(first)
+  {
+    let ones = word.count_ones() as usize;
+    if rank < ones {
+      let mut bits = word;
+      for _ in 0..rank {
+        bits &= bits - 1;
+      }
+      return Some(index * WORD_BITS + bits.trailing_zeros() as usize);
+    }
+    rank -= ones;
+  }
+  None
+}
+
+/// Iterator over the set bits of a bitmap, from [`BitmapOps::iter_ones`].
+#[derive(Debug, Clone)]
+pub struct Ones<'a> {
+  /// Words being iterated.
+  words: &'a [usize],
+  /// Lowest index not yet visited.
+  front: usize,
+  /// One past the highest index not yet visited.
+  back: usize,
+}
+
+impl Iterator for Ones<'_> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    let found = find_next(self.words, self.front, self.back, |word| word);
+    self.front = found.map_or(self.back, |index| index + 1);
+    found
+  }
+}
+
+impl DoubleEndedIterator for Ones<'_> {
+  fn next_back(&mut self) -> Option<usize> {
+    let found = find_prev(self.words, self.front, self.back, |word| word);
+    self.back = found.unwrap_or(self.front);
+    found
+  }
+}
+
+impl FusedIterator for Ones<'_> {}
+
+/// Iterator over the clear bits of a bitmap, from [`BitmapOps::iter_zeros`].
+#[derive(Debug, Clone)]
+pub struct Zeros<'a> {
+  /// Words being iterated.
+  words: &'a [usize],
+  /// Lowest index not yet visited.
+  front: usize,
+  /// One past the highest index not yet visited.
+  back: usize,
+}
+
+impl Iterator for Zeros<'_> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    let found = find_next(self.words, self.front, self.back, |word| !word);
+    self.front = found.map_or(self.back, |index| index + 1);
+    found
+  }
+}
+
+impl DoubleEndedIterator for Zeros<'_> {
+  fn next_back(&mut self) -> Option<usize> {
+    let found = find_prev(self.words, self.front, self.back, |word| !word);
+    self.back = found.unwrap_or(self.front);
+    found
+  }
+}
+
+impl FusedIterator for Zeros<'_> {}
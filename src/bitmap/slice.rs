@@ -0,0 +1,97 @@
+//! Bitmap over caller-provided word storage.
+
+use super::{
+  ops::{
+    Storage,
+    Token,
+  },
+  BitmapError,
+  WORD_BITS,
+};
+
+/// A fixed-length bitmap over a borrowed `&mut [usize]`.
+///
+/// Needs no allocator, so it can be used from interrupt handlers or over
+/// statically reserved memory. The query and mutation API comes from
+/// [`BitmapOps`](super::BitmapOps).
+#[derive(Debug)]
+pub struct BitmapRef<'a> {
+  /// Words holding the bitmap bits.
+  words: &'a mut [usize],
+  /// Number of bits in use; bits at and past this index are always zero.
+  len: usize,
+}
+
+impl<'a> BitmapRef<'a> {
+  /// Clear `words` and use all of their bits.
+  pub fn new(words: &'a mut [usize]) -> Self {
+    let len = words.len() * WORD_BITS;
+    Self::with_len(words, len)
+  }
+
+  /// Try to clear `words` and use the first `len` bits.
+  pub fn try_with_len(words: &'a mut [usize], len: usize) -> Result<Self, BitmapError> {
+    if len.div_ceil(WORD_BITS) > words.len() {
+      return Err(BitmapError::InvalidSize);
+    }
+    words.fill(0);
+    Ok(Self { words, len })
+  }
+
+  /// Clear `words` and use the first `len` bits, panicking if they do not fit.
+  pub fn with_len(words: &'a mut [usize], len: usize) -> Self {
+    Self::try_with_len(words, len).expect("BitmapRef storage too small")
+  }
+
+  /// Use all bits of `words`, keeping their current contents.
+  pub const fn from_words(words: &'a mut [usize]) -> Self {
+    let len = words.len() * WORD_BITS;
+    Self { words, len }
+  }
+
+  /// Release the borrowed storage.
+  pub fn into_words(self) -> &'a mut [usize] {
+    self.words
+  }
+}
+
+impl Storage for BitmapRef<'_> {
+  fn bit_len(&self) -> usize {
+    self.len
+  }
+
+  fn raw_words(&self) -> &[usize] {
+    &self.words[..self.len.div_ceil(WORD_BITS)]
+  }
+
+  fn raw_words_mut(&mut self, _: Token) -> &mut [usize] {
+    &mut self.words[..self.len.div_ceil(WORD_BITS)]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bitmap::BitmapOps;
+
+  #[test]
+  fn test_bitmap_ref() {
+    let mut storage = [usize::MAX; 3];
+    let mut bitmap = BitmapRef::with_len(&mut storage, 100);
+    assert_eq!(bitmap.len(), 100);
+    assert_eq!(bitmap.first_set(), None);
+    assert!(bitmap.try_set(100).is_err());
+
+    bitmap.set_range(60..70);
+    bitmap.toggle(99);
+    assert_eq!(bitmap.iter_ones().count(), 11);
+    assert_eq!(bitmap.last_set(), Some(99));
+
+    let words = bitmap.into_words();
+    assert_eq!(words[2], 0);
+    let bitmap = BitmapRef::from_words(words);
+    assert_eq!(bitmap.len(), 3 * WORD_BITS);
+    assert_eq!(bitmap.first_set(), Some(60));
+    assert!(BitmapRef::try_with_len(&mut [0; 1], WORD_BITS + 1).is_err());
+  }
+}
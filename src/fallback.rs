@@ -0,0 +1,228 @@
+//! Allocator combinator that falls back to a second allocator.
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::ptr::{
+  self,
+  NonNull,
+};
+
+use crate::owns::Owns;
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Allocator that serves requests from `P` and falls back to `S` when `P`
+/// cannot satisfy them.
+///
+/// Deallocation and resizing are routed by asking the primary allocator
+/// whether it [`Owns`] the pointer. A block that cannot grow inside the
+/// primary allocator is moved to the secondary one.
+pub struct Fallback<P, S> {
+  /// Allocator tried first.
+  primary: P,
+  /// Allocator used when the primary one fails.
+  secondary: S,
+}
+
+impl<P, S> Fallback<P, S> {
+  /// Combine `primary` with `secondary` as its fallback.
+  pub const fn new(primary: P, secondary: S) -> Self {
+    Self { primary, secondary }
+  }
+
+  /// The allocator tried first.
+  pub fn primary(&self) -> &P {
+    &self.primary
+  }
+
+  /// The allocator used when the primary one fails.
+  pub fn secondary(&self) -> &S {
+    &self.secondary
+  }
+}
+
+impl<P, S> Fallback<P, S>
+where
+  P: Allocator + Owns,
+  S: Allocator,
+{
+  /// Move a block owned by the primary allocator into the secondary one.
+  unsafe fn migrate(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+    zeroed: bool,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let new_ptr = if zeroed {
+      self.secondary.allocate_zeroed(new_layout)?
+    } else {
+      self.secondary.allocate(new_layout)?
+    };
+
+    let len = old_layout.size().min(new_layout.size());
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), len);
+      self.primary.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+}
+
+impl<P, S> Owns for Fallback<P, S>
+where
+  P: Owns,
+  S: Owns,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.primary.owns(ptr) || self.secondary.owns(ptr)
+  }
+}
+
+unsafe impl<P, S> Allocator for Fallback<P, S>
+where
+  P: Allocator + Owns,
+  S: Allocator,
+{
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    self
+      .primary
+      .allocate(layout)
+      .or_else(|_| self.secondary.allocate(layout))
+  }
+
+  fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    self
+      .primary
+      .allocate_zeroed(layout)
+      .or_else(|_| self.secondary.allocate_zeroed(layout))
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    unsafe {
+      if self.primary.owns(ptr) {
+        self.primary.deallocate(ptr, layout)
+      } else {
+        self.secondary.deallocate(ptr, layout)
+      }
+    }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe {
+      if !self.primary.owns(ptr) {
+        return self.secondary.grow(ptr, old_layout, new_layout);
+      }
+      match self.primary.grow(ptr, old_layout, new_layout) {
+        Ok(new_ptr) => Ok(new_ptr),
+        Err(_) => self.migrate(ptr, old_layout, new_layout, false),
+      }
+    }
+  }
+
+  unsafe fn grow_zeroed(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe {
+      if !self.primary.owns(ptr) {
+        return self.secondary.grow_zeroed(ptr, old_layout, new_layout);
+      }
+      match self.primary.grow_zeroed(ptr, old_layout, new_layout) {
+        Ok(new_ptr) => Ok(new_ptr),
+        Err(_) => self.migrate(ptr, old_layout, new_layout, true),
+      }
+    }
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe {
+      if !self.primary.owns(ptr) {
+        return self.secondary.shrink(ptr, old_layout, new_layout);
+      }
+      match self.primary.shrink(ptr, old_layout, new_layout) {
+        Ok(new_ptr) => Ok(new_ptr),
+        Err(_) => self.migrate(ptr, old_layout, new_layout, false),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::fixed::FixedAllocator;
+  use alloc::{
+    alloc::Global,
+    vec::Vec,
+  };
+
+  #[test]
+  fn test_fallback_allocate() {
+    let mut buffer = [0u8; 64];
+    let fixed = FixedAllocator::new(&mut buffer);
+    let fallback = Fallback::new(&fixed, Global);
+
+    let small = Layout::new::<u64>();
+    let ptr = fallback.allocate(small).unwrap();
+    assert!(fixed.owns(ptr.cast()));
+
+    let large = Layout::from_size_align(1024, 8).unwrap();
+    let big = fallback.allocate(large).unwrap();
+    assert!(!fixed.owns(big.cast()));
+
+    unsafe {
+      fallback.deallocate(big.cast(), large);
+      fallback.deallocate(ptr.cast(), small);
+    }
+    assert_eq!(fixed.used(), 0);
+  }
+
+  #[test]
+  fn test_fallback_migrates_on_grow() {
+    let mut buffer = [0u8; 128];
+    let fixed = FixedAllocator::new(&mut buffer);
+    let fallback = Fallback::new(&fixed, Global);
+
+    let mut values = Vec::new_in(&fallback);
+    values.push(0u32);
+    assert!(fixed.owns(NonNull::from(&values[0]).cast()));
+
+    values.extend(1..1000);
+    assert!(!fixed.owns(NonNull::from(&values[0]).cast()));
+    assert!(values.iter().copied().eq(0..1000));
+    assert_eq!(fixed.used(), 0);
+  }
+
+  #[test]
+  fn test_fallback_owns() {
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    let primary = FixedAllocator::new(&mut first);
+    let secondary = FixedAllocator::new(&mut second);
+    let fallback = Fallback::new(&primary, &secondary);
+
+    let layout = Layout::from_size_align(24, 8).unwrap();
+    let a = fallback.allocate(layout).unwrap();
+    let b = fallback.allocate(layout).unwrap();
+    assert!(primary.owns(a.cast()) && !primary.owns(b.cast()));
+    assert!(fallback.owns(a.cast()) && fallback.owns(b.cast()));
+
+    let outside = 0u64;
+    assert!(!fallback.owns(NonNull::from(&outside).cast()));
+  }
+}
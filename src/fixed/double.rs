@@ -0,0 +1,375 @@
+//! Fixed buffer shared by a bottom-up and a top-down bump allocator.
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::{
+  cell::UnsafeCell,
+  ptr,
+  ptr::NonNull,
+};
+
+use crate::owns::Owns;
+
+#[derive(Debug)]
+struct DoubleInner<'fixed> {
+  mem: &'fixed mut [u8],
+  /// End of the low region, growing upwards.
+  low: usize,
+  /// Start of the high region, growing downwards.
+  high: usize,
+}
+
+impl DoubleInner<'_> {
+  fn base(&self) -> usize {
+    self.mem.as_ptr() as usize
+  }
+
+  fn offset_of(&self, ptr: NonNull<u8>) -> Option<usize> {
+    let ptr_addr = ptr.as_ptr() as usize;
+    let mem_start = self.base();
+
+    if ptr_addr < mem_start || ptr_addr >= mem_start + self.mem.len() {
+      return None;
+    }
+    Some(ptr_addr - mem_start)
+  }
+
+  fn ptr_at(&mut self, offset: usize) -> NonNull<u8> {
+    unsafe { NonNull::new_unchecked(self.mem.as_mut_ptr().add(offset)) }
+  }
+}
+
+/// Allocator serving long-lived data from the bottom of a buffer and
+/// short-lived data from the top.
+///
+/// The two ends are reached through [`low`](Self::low) and
+/// [`high`](Self::high), which both implement [`Allocator`]. Allocation
+/// fails once the two regions would overlap.
+///
+/// High-end blocks with an alignment above one are preceded by a
+/// `usize` header holding the previous top of the high region, so freeing
+/// them in reverse order also gives back their alignment padding.
+pub struct DoubleEndedAllocator<'fixed> {
+  /// Interior mutable state tracking both ends.
+  inner: UnsafeCell<DoubleInner<'fixed>>,
+}
+
+impl<'fixed> DoubleEndedAllocator<'fixed> {
+  /// Create a new allocator from the given memory slice.
+  pub const fn new(mem: &'fixed mut [u8]) -> Self {
+    let high = mem.len();
+    Self {
+      inner: UnsafeCell::new(DoubleInner {
+        mem,
+        low: 0,
+        high,
+      }),
+    }
+  }
+
+  fn get(&self) -> &DoubleInner<'fixed> {
+    unsafe { &*self.inner.get() }
+  }
+
+  fn get_mut(&self) -> &mut DoubleInner<'fixed> {
+    unsafe { &mut *self.inner.get() }
+  }
+
+  /// Handle allocating from the bottom of the buffer.
+  pub fn low(&self) -> LowEnd<'_, 'fixed> {
+    LowEnd { alloc: self }
+  }
+
+  /// Handle allocating from the top of the buffer.
+  pub fn high(&self) -> HighEnd<'_, 'fixed> {
+    HighEnd { alloc: self }
+  }
+
+  /// Total capacity of the underlying buffer.
+  pub fn capacity(&self) -> usize {
+    self.get().mem.len()
+  }
+
+  /// Bytes used by the low end.
+  pub fn low_used(&self) -> usize {
+    self.get().low
+  }
+
+  /// Bytes used by the high end.
+  pub fn high_used(&self) -> usize {
+    let inner = self.get();
+    inner.mem.len() - inner.high
+  }
+
+  /// Bytes left between the two ends.
+  pub fn available(&self) -> usize {
+    let inner = self.get();
+    inner.high - inner.low
+  }
+
+  /// Reset the low end, forgetting its allocations.
+  ///
+  /// # Safety
+  /// Caller must ensure no memory allocated from the low end is still in use.
+  pub unsafe fn reset_low(&self) {
+    self.get_mut().low = 0;
+  }
+
+  /// Reset the high end, forgetting its allocations.
+  ///
+  /// # Safety
+  /// Caller must ensure no memory allocated from the high end is still in use.
+  pub unsafe fn reset_high(&self) {
+    let inner = self.get_mut();
+    inner.high = inner.mem.len();
+  }
+
+  /// Reset both ends, forgetting all previous allocations.
+  ///
+  /// # Safety
+  /// Caller must ensure no allocated memory is still in use.
+  pub unsafe fn reset(&self) {
+    unsafe {
+      self.reset_low();
+      self.reset_high();
+    }
+  }
+}
+
+impl Owns for DoubleEndedAllocator<'_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.get().offset_of(ptr).is_some()
+  }
+}
+
+#[derive(Clone, Copy)]
+/// Bottom-up allocation handle of a [`DoubleEndedAllocator`].
+pub struct LowEnd<'a, 'fixed> {
+  /// Allocator owning the buffer.
+  alloc: &'a DoubleEndedAllocator<'fixed>,
+}
+
+impl Owns for LowEnd<'_, '_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self
+      .alloc
+      .get()
+      .offset_of(ptr)
+      .is_some_and(|offset| offset < self.alloc.get().low)
+  }
+}
+
+unsafe impl Allocator for LowEnd<'_, '_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.alloc.get_mut();
+
+    let base = inner.base();
+    let align = layout.align();
+    let aligned_start = ((base + inner.low + align - 1) & !(align - 1)) - base;
+
+    let end = aligned_start.checked_add(layout.size()).ok_or(AllocError)?;
+    if end > inner.high {
+      return Err(AllocError);
+    }
+
+    inner.low = end;
+    Ok(NonNull::slice_from_raw_parts(inner.ptr_at(aligned_start), layout.size()))
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    let inner = self.alloc.get_mut();
+
+    if let Some(offset) = inner.offset_of(ptr)
+      && offset + layout.size() == inner.low
+    {
+      inner.low = offset;
+    }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.alloc.get_mut();
+    let offset = inner.offset_of(ptr).ok_or(AllocError)?;
+
+    if offset + old_layout.size() == inner.low
+      && (ptr.as_ptr() as usize).is_multiple_of(new_layout.align())
+      && offset + new_layout.size() <= inner.high
+    {
+      inner.low = offset + new_layout.size();
+      return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), old_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.alloc.get_mut();
+    let offset = inner.offset_of(ptr).ok_or(AllocError)?;
+
+    if !(ptr.as_ptr() as usize).is_multiple_of(new_layout.align()) {
+      let new_ptr = self.allocate(new_layout)?;
+      unsafe {
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), new_layout.size());
+        self.deallocate(ptr, old_layout);
+      }
+      return Ok(new_ptr);
+    }
+
+    if offset + old_layout.size() == inner.low {
+      inner.low = offset + new_layout.size();
+    }
+    Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+  }
+}
+
+/// Size of the header in front of a high-end block of `layout`.
+///
+/// Blocks aligned to one byte never need padding, so their previous top is
+/// simply their end.
+fn high_header(layout: Layout) -> usize {
+  if layout.align() > 1 {
+    size_of::<usize>()
+  } else {
+    0
+  }
+}
+
+#[derive(Clone, Copy)]
+/// Top-down allocation handle of a [`DoubleEndedAllocator`].
+pub struct HighEnd<'a, 'fixed> {
+  /// Allocator owning the buffer.
+  alloc: &'a DoubleEndedAllocator<'fixed>,
+}
+
+impl HighEnd<'_, '_> {
+  /// Offsets of the header and the block for `layout` placed below the
+  /// current top, if it fits above the low end.
+  fn place(&self, layout: Layout) -> Option<(usize, usize)> {
+    let inner = self.alloc.get();
+    let base = inner.base();
+    let end = (base + inner.high).checked_sub(layout.size())?;
+    let start = (end & !(layout.align() - 1)).checked_sub(base)?;
+    let header = start.checked_sub(high_header(layout))?;
+    (header >= inner.low).then_some((header, start))
+  }
+
+  /// Claim the space found by [`place`](Self::place), recording the
+  /// previous top in the header.
+  fn push(&self, layout: Layout, header: usize, start: usize) -> NonNull<[u8]> {
+    let inner = self.alloc.get_mut();
+    if high_header(layout) > 0 {
+      unsafe { inner.ptr_at(header).cast::<usize>().write_unaligned(inner.high) };
+    }
+    inner.high = header;
+    NonNull::slice_from_raw_parts(inner.ptr_at(start), layout.size())
+  }
+
+  /// Top of the high region from before the block at `offset` was
+  /// allocated, if that block is the most recent one.
+  fn previous_top(&self, offset: usize, layout: Layout) -> Option<usize> {
+    let inner = self.alloc.get_mut();
+    let header = offset.checked_sub(high_header(layout))?;
+    if header != inner.high {
+      return None;
+    }
+    if high_header(layout) > 0 {
+      Some(unsafe { inner.ptr_at(header).cast::<usize>().read_unaligned() })
+    } else {
+      Some(offset + layout.size())
+    }
+  }
+}
+
+impl Owns for HighEnd<'_, '_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self
+      .alloc
+      .get()
+      .offset_of(ptr)
+      .is_some_and(|offset| offset >= self.alloc.get().high)
+  }
+}
+
+unsafe impl Allocator for HighEnd<'_, '_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let (header, start) = self.place(layout).ok_or(AllocError)?;
+    Ok(self.push(layout, header, start))
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    if let Some(offset) = self.alloc.get().offset_of(ptr)
+      && let Some(previous) = self.previous_top(offset, layout)
+    {
+      self.alloc.get_mut().high = previous;
+    }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let offset = self.alloc.get().offset_of(ptr).ok_or(AllocError)?;
+
+    // The most recent block is popped and pushed again with the new size.
+    // The new block may overlap the old one, so the data is moved before
+    // the new header is written.
+    if let Some(previous) = self.previous_top(offset, old_layout) {
+      let top = core::mem::replace(&mut self.alloc.get_mut().high, previous);
+      let Some((header, start)) = self.place(new_layout) else {
+        self.alloc.get_mut().high = top;
+        return Err(AllocError);
+      };
+      unsafe {
+        let new_ptr = self.alloc.get_mut().ptr_at(start);
+        ptr::copy(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+      }
+      return Ok(self.push(new_layout, header, start));
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), old_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    if (ptr.as_ptr() as usize).is_multiple_of(new_layout.align())
+      && high_header(old_layout) == high_header(new_layout)
+    {
+      return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), new_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+}
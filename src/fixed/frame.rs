@@ -0,0 +1,185 @@
+//! Multi-buffered per-frame scratch allocator.
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::{
+  cell::Cell,
+  ptr::NonNull,
+};
+
+use super::FixedAllocator;
+use crate::owns::Owns;
+
+/// Splits a buffer into `FRAMES` [`FixedAllocator`] regions used round-robin,
+/// one per frame.
+///
+/// [`next_frame`](Self::next_frame) hands out a [`FrameRegion`] for the
+/// oldest region after resetting it. A region is only reused once the
+/// handle of the frame that last used it has been dropped, and dropping a
+/// handle requires every allocation borrowing it to be gone, so a
+/// collection that owns its frame handle frees the region when dropped. With two
+/// frames, data from the previous frame therefore stays usable for exactly
+/// one more frame:
+///
+/// ```
+/// #![feature(allocator_api)]
+/// use heaped::fixed::FrameAllocator;
+///
+/// let mut buffer = [0u8; 1024];
+/// let frames = FrameAllocator::<2>::new(&mut buffer);
+///
+/// let mut previous = Vec::new_in(frames.next_frame());
+/// for _ in 0..4 {
+///   let mut current = Vec::new_in(frames.next_frame());
+///   current.extend(previous.iter().map(|x| x + 1));
+///   current.push(0u32);
+///   previous = current;
+/// }
+/// assert_eq!(previous, [3, 2, 1, 0]);
+/// ```
+pub struct FrameAllocator<'fixed, const FRAMES: usize> {
+  /// One bump allocator per frame slot.
+  regions: [FixedAllocator<'fixed>; FRAMES],
+  /// Whether a handle for each region is currently alive.
+  in_use: [Cell<bool>; FRAMES],
+  /// Number of frames started so far.
+  frames: Cell<u64>,
+}
+
+impl<'fixed, const FRAMES: usize> FrameAllocator<'fixed, FRAMES> {
+  /// Split `mem` into `FRAMES` equally sized regions.
+  pub fn new(mem: &'fixed mut [u8]) -> Self {
+    const { assert!(FRAMES > 0, "FrameAllocator needs at least one frame") };
+
+    let region_len = mem.len() / FRAMES;
+    let mut rest = mem;
+    let regions = core::array::from_fn(|_| {
+      let (region, tail) = core::mem::take(&mut rest).split_at_mut(region_len);
+      rest = tail;
+      FixedAllocator::new(region)
+    });
+
+    Self {
+      regions,
+      in_use: core::array::from_fn(|_| Cell::new(false)),
+      frames: Cell::new(0),
+    }
+  }
+
+  /// Capacity of each frame region.
+  pub fn frame_capacity(&self) -> usize {
+    self.regions[0].capacity()
+  }
+
+  /// Number of frames started so far.
+  pub fn frame_count(&self) -> u64 {
+    self.frames.get()
+  }
+
+  /// Try to start the next frame, resetting its region.
+  ///
+  /// Returns `None` if the handle of the frame that last used the region is
+  /// still alive.
+  pub fn try_next_frame(&self) -> Option<FrameRegion<'_, 'fixed>> {
+    let number = self.frames.get();
+    let index = (number % FRAMES as u64) as usize;
+    if self.in_use[index].get() {
+      return None;
+    }
+
+    // SAFETY: The region's previous handle was dropped, which ends every
+    // borrow of memory allocated through it.
+    unsafe { self.regions[index].reset() };
+    self.in_use[index].set(true);
+    self.frames.set(number + 1);
+
+    Some(FrameRegion {
+      region: &self.regions[index],
+      in_use: &self.in_use[index],
+      number,
+    })
+  }
+
+  /// Start the next frame, resetting its region.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the [`FrameRegion`] from `FRAMES` frames ago, which shares
+  /// the region with this frame, has not been dropped yet. Use
+  /// [`try_next_frame`](Self::try_next_frame) to handle that case instead.
+  pub fn next_frame(&self) -> FrameRegion<'_, 'fixed> {
+    self
+      .try_next_frame()
+      .expect("FrameAllocator region is still in use by an older frame")
+  }
+}
+
+/// Allocation handle for one frame of a [`FrameAllocator`].
+pub struct FrameRegion<'a, 'fixed> {
+  /// Region backing this frame.
+  region: &'a FixedAllocator<'fixed>,
+  /// Cleared when the handle is dropped.
+  in_use: &'a Cell<bool>,
+  /// Sequence number of the frame.
+  number: u64,
+}
+
+impl FrameRegion<'_, '_> {
+  /// Sequence number of this frame, starting at zero.
+  pub fn number(&self) -> u64 {
+    self.number
+  }
+
+  /// Bytes allocated during this frame.
+  pub fn used(&self) -> usize {
+    self.region.used()
+  }
+
+  /// Bytes still available in this frame.
+  pub fn available(&self) -> usize {
+    self.region.available()
+  }
+}
+
+impl Drop for FrameRegion<'_, '_> {
+  fn drop(&mut self) {
+    self.in_use.set(false);
+  }
+}
+
+impl Owns for FrameRegion<'_, '_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.region.owns(ptr)
+  }
+}
+
+unsafe impl Allocator for FrameRegion<'_, '_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    self.region.allocate(layout)
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    unsafe { self.region.deallocate(ptr, layout) }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.region.grow(ptr, old_layout, new_layout) }
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.region.shrink(ptr, old_layout, new_layout) }
+  }
+}
@@ -0,0 +1,403 @@
+//! Fixed-buffer allocator that recycles freed blocks through a free list.
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::{
+  cell::UnsafeCell,
+  ptr,
+  ptr::NonNull,
+};
+
+use crate::owns::Owns;
+
+/// Granularity of every block. Large enough to hold a [`FreeBlock`], so any
+/// gap left over by splitting a block can itself become a free block.
+const GRANULE: usize = 16;
+
+/// Marks the end of the free list.
+const NONE: usize = usize::MAX;
+
+/// Header written at the start of every free block.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct FreeBlock {
+  /// Size of the block in bytes, including this header.
+  size: usize,
+  /// Offset of the next free block, or [`NONE`].
+  next: usize,
+}
+
+const _: () = assert!(size_of::<FreeBlock>() <= GRANULE && align_of::<FreeBlock>() <= GRANULE);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Strategy for picking a free block to serve an allocation.
+pub enum FitPolicy {
+  /// Use the first block that is large enough.
+  #[default]
+  FirstFit,
+  /// Use the block that leaves the least space over.
+  BestFit,
+}
+
+#[derive(Debug)]
+struct FreeListInner<'fixed> {
+  mem: &'fixed mut [u8],
+  /// Offset of the first usable, granule-aligned byte.
+  start: usize,
+  /// Offset one past the last usable byte.
+  end: usize,
+  /// Offset of the first free block, sorted by address.
+  head: usize,
+  used: usize,
+  policy: FitPolicy,
+}
+
+impl FreeListInner<'_> {
+  fn base(&self) -> usize {
+    self.mem.as_ptr() as usize
+  }
+
+  fn block(&self, offset: usize) -> FreeBlock {
+    unsafe { self.mem.as_ptr().add(offset).cast::<FreeBlock>().read() }
+  }
+
+  fn set_block(&mut self, offset: usize, block: FreeBlock) {
+    unsafe { self.mem.as_mut_ptr().add(offset).cast::<FreeBlock>().write(block) }
+  }
+
+  /// Point `prev` (or the list head) at `next`.
+  fn link(&mut self, prev: usize, next: usize) {
+    if prev == NONE {
+      self.head = next;
+    } else {
+      let mut block = self.block(prev);
+      block.next = next;
+      self.set_block(prev, block);
+    }
+  }
+
+  fn reset(&mut self) {
+    self.used = 0;
+    self.head = NONE;
+    if self.end > self.start {
+      self.head = self.start;
+      self.set_block(
+        self.start,
+        FreeBlock {
+          size: self.end - self.start,
+          next: NONE,
+        },
+      );
+    }
+  }
+
+  fn offset_of(&self, ptr: NonNull<u8>) -> Option<usize> {
+    let ptr_addr = ptr.as_ptr() as usize;
+    let mem_start = self.base();
+
+    if ptr_addr < mem_start + self.start || ptr_addr >= mem_start + self.end {
+      return None;
+    }
+    Some(ptr_addr - mem_start)
+  }
+
+  /// Find a free block able to hold `size` bytes at `align`, returning the
+  /// previous block, the block and the aligned start offset.
+  fn find(&self, size: usize, align: usize) -> Option<(usize, usize, usize)> {
+    let base = self.base();
+    let mut found = None;
+    let mut found_waste = usize::MAX;
+
+    let mut prev = NONE;
+    let mut cur = self.head;
+    while cur != NONE {
+      let block = self.block(cur);
+      let start = ((base + cur + align - 1) & !(align - 1)) - base;
+      if start
+        .checked_add(size)
+        .is_some_and(|end| end <= cur + block.size)
+      {
+        let waste = block.size - size;
+        if waste < found_waste {
+          found = Some((prev, cur, start));
+          found_waste = waste;
+        }
+        if self.policy == FitPolicy::FirstFit || waste == 0 {
+          break;
+        }
+      }
+      prev = cur;
+      cur = block.next;
+    }
+    found
+  }
+
+  /// Take `size` bytes at `start` out of the free block at `cur`, returning
+  /// the space before and after to the list.
+  fn carve(&mut self, prev: usize, cur: usize, start: usize, size: usize) {
+    let block = self.block(cur);
+    let front = start - cur;
+    let back_offset = start + size;
+    let back = cur + block.size - back_offset;
+
+    let mut next = block.next;
+    if back > 0 {
+      self.set_block(back_offset, FreeBlock { size: back, next });
+      next = back_offset;
+    }
+    if front > 0 {
+      self.set_block(cur, FreeBlock { size: front, next });
+      next = cur;
+    }
+    self.link(prev, next);
+    self.used += size;
+  }
+
+  /// Return `size` bytes at `offset` to the list, merging with neighbors.
+  fn release(&mut self, offset: usize, size: usize) {
+    let mut prev = NONE;
+    let mut cur = self.head;
+    while cur != NONE && cur < offset {
+      prev = cur;
+      cur = self.block(cur).next;
+    }
+    debug_assert!(cur == NONE || offset + size <= cur, "double free in free list");
+
+    let mut block = FreeBlock { size, next: cur };
+    if cur != NONE && offset + size == cur {
+      let next = self.block(cur);
+      block.size += next.size;
+      block.next = next.next;
+    }
+
+    self.used -= size;
+    if prev != NONE {
+      let before = self.block(prev);
+      debug_assert!(prev + before.size <= offset, "double free in free list");
+      if prev + before.size == offset {
+        self.set_block(
+          prev,
+          FreeBlock {
+            size: before.size + block.size,
+            next: block.next,
+          },
+        );
+        return;
+      }
+    }
+
+    self.set_block(offset, block);
+    self.link(prev, offset);
+  }
+
+  /// Find the free block starting exactly at `offset`.
+  fn find_at(&self, offset: usize) -> Option<(usize, usize)> {
+    let mut prev = NONE;
+    let mut cur = self.head;
+    while cur != NONE && cur < offset {
+      prev = cur;
+      cur = self.block(cur).next;
+    }
+    (cur == offset).then_some((prev, cur))
+  }
+}
+
+/// Rounded size and alignment used for a block serving `layout`.
+fn block_size(layout: Layout) -> Result<(usize, usize), AllocError> {
+  let size = layout
+    .size()
+    .max(1)
+    .checked_next_multiple_of(GRANULE)
+    .ok_or(AllocError)?;
+  Ok((size, layout.align().max(GRANULE)))
+}
+
+/// Allocator over a fixed slice that reuses freed memory.
+///
+/// Unlike [`FixedAllocator`](super::FixedAllocator), any freed block is
+/// threaded into an address-ordered free list stored inside the buffer
+/// itself and merged with free neighbors. Every block is rounded up to 16
+/// bytes.
+pub struct FixedFreeListAllocator<'fixed> {
+  /// Interior mutable state tracking the buffer.
+  inner: UnsafeCell<FreeListInner<'fixed>>,
+}
+
+impl<'fixed> FixedFreeListAllocator<'fixed> {
+  /// Create a new first-fit allocator from the given memory slice.
+  pub fn new(mem: &'fixed mut [u8]) -> Self {
+    Self::with_policy(mem, FitPolicy::FirstFit)
+  }
+
+  /// Create a new allocator using the given fit policy.
+  pub fn with_policy(mem: &'fixed mut [u8], policy: FitPolicy) -> Self {
+    let len = mem.len();
+    let start = mem.as_ptr().align_offset(GRANULE).min(len);
+    let end = start + (len - start) / GRANULE * GRANULE;
+    let mut inner = FreeListInner {
+      mem,
+      start,
+      end,
+      head: NONE,
+      used: 0,
+      policy,
+    };
+    inner.reset();
+
+    Self {
+      inner: UnsafeCell::new(inner),
+    }
+  }
+
+  fn get(&self) -> &FreeListInner<'fixed> {
+    unsafe { &*self.inner.get() }
+  }
+
+  fn get_mut(&self) -> &mut FreeListInner<'fixed> {
+    unsafe { &mut *self.inner.get() }
+  }
+
+  /// Fit policy used to pick free blocks.
+  pub fn policy(&self) -> FitPolicy {
+    self.get().policy
+  }
+
+  /// Usable capacity of the underlying buffer.
+  pub fn capacity(&self) -> usize {
+    let inner = self.get();
+    inner.end - inner.start
+  }
+
+  /// Amount of memory currently allocated, including rounding.
+  pub fn used(&self) -> usize {
+    self.get().used
+  }
+
+  /// Free bytes, possibly split across several blocks.
+  pub fn available(&self) -> usize {
+    self.capacity() - self.used()
+  }
+
+  /// Size of the largest free block.
+  pub fn largest_free_block(&self) -> usize {
+    let inner = self.get();
+    let mut largest = 0;
+    let mut cur = inner.head;
+    while cur != NONE {
+      let block = inner.block(cur);
+      largest = largest.max(block.size);
+      cur = block.next;
+    }
+    largest
+  }
+
+  /// Number of separate free blocks.
+  pub fn free_blocks(&self) -> usize {
+    let inner = self.get();
+    let mut count = 0;
+    let mut cur = inner.head;
+    while cur != NONE {
+      count += 1;
+      cur = inner.block(cur).next;
+    }
+    count
+  }
+
+  /// Reset the allocator, forgetting all previous allocations.
+  ///
+  /// # Safety
+  /// Caller must ensure no allocated memory is still in use.
+  pub unsafe fn reset(&self) {
+    self.get_mut().reset();
+  }
+}
+
+impl Owns for FixedFreeListAllocator<'_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.get().offset_of(ptr).is_some()
+  }
+}
+
+unsafe impl Allocator for FixedFreeListAllocator<'_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.get_mut();
+    let (size, align) = block_size(layout)?;
+
+    let (prev, cur, start) = inner.find(size, align).ok_or(AllocError)?;
+    inner.carve(prev, cur, start, size);
+
+    let ptr = unsafe { NonNull::new_unchecked(inner.mem.as_mut_ptr().add(start)) };
+    Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    let inner = self.get_mut();
+    let Some(offset) = inner.offset_of(ptr) else {
+      return;
+    };
+    let Ok((size, _)) = block_size(layout) else {
+      return;
+    };
+    inner.release(offset, size);
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.get_mut();
+    let offset = inner.offset_of(ptr).ok_or(AllocError)?;
+    let (old_size, _) = block_size(old_layout)?;
+    let (new_size, new_align) = block_size(new_layout)?;
+
+    if (ptr.as_ptr() as usize).is_multiple_of(new_align) {
+      if new_size == old_size {
+        return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+      }
+      if let Some((prev, next)) = inner.find_at(offset + old_size)
+        && inner.block(next).size >= new_size - old_size
+      {
+        inner.carve(prev, next, next, new_size - old_size);
+        return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+      }
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), old_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let inner = self.get_mut();
+    let offset = inner.offset_of(ptr).ok_or(AllocError)?;
+    let (old_size, _) = block_size(old_layout)?;
+    let (new_size, new_align) = block_size(new_layout)?;
+
+    if (ptr.as_ptr() as usize).is_multiple_of(new_align) {
+      if new_size < old_size {
+        inner.release(offset + new_size, old_size - new_size);
+      }
+      return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), new_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+}
@@ -0,0 +1,166 @@
+//! Bump allocator owning its buffer inline.
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::{
+  cell::{
+    Cell,
+    UnsafeCell,
+  },
+  mem::MaybeUninit,
+  ptr,
+  ptr::NonNull,
+};
+
+use crate::owns::Owns;
+
+/// Storage aligned so common allocations need no padding.
+#[repr(C, align(16))]
+struct InlineBuffer<const N: usize>([MaybeUninit<u8>; N]);
+
+/// Bump allocator over an inline buffer of `N` bytes.
+///
+/// Works like [`FixedAllocator`](super::FixedAllocator) but owns its memory,
+/// so it can live inside a struct without a borrowed buffer. Only
+/// `&InlineAllocator` implements [`Allocator`]: allocations borrow the
+/// allocator, which keeps the buffer from moving while they are alive.
+pub struct InlineAllocator<const N: usize> {
+  /// Backing storage.
+  mem: UnsafeCell<InlineBuffer<N>>,
+  /// Amount of memory already allocated.
+  used: Cell<usize>,
+}
+
+impl<const N: usize> InlineAllocator<N> {
+  /// Create a new, empty allocator.
+  pub const fn new() -> Self {
+    Self {
+      mem: UnsafeCell::new(InlineBuffer([MaybeUninit::uninit(); N])),
+      used: Cell::new(0),
+    }
+  }
+
+  fn base(&self) -> *mut u8 {
+    self.mem.get().cast::<u8>()
+  }
+
+  fn offset_of(&self, ptr: NonNull<u8>) -> Option<usize> {
+    let ptr_addr = ptr.as_ptr() as usize;
+    let mem_start = self.base() as usize;
+
+    if ptr_addr < mem_start || ptr_addr >= mem_start + N {
+      return None;
+    }
+    Some(ptr_addr - mem_start)
+  }
+
+  /// Total capacity of the inline buffer.
+  pub const fn capacity(&self) -> usize {
+    N
+  }
+
+  /// Amount of memory already allocated.
+  pub fn used(&self) -> usize {
+    self.used.get()
+  }
+
+  /// Remaining capacity in bytes.
+  pub fn available(&self) -> usize {
+    N - self.used.get()
+  }
+
+  /// Reset the allocator, forgetting all previous allocations.
+  ///
+  /// # Safety
+  /// Caller must ensure no allocated memory is still in use.
+  pub unsafe fn reset(&self) {
+    self.used.set(0);
+  }
+}
+
+impl<const N: usize> Default for InlineAllocator<N> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const N: usize> Owns for InlineAllocator<N> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.offset_of(ptr).is_some()
+  }
+}
+
+unsafe impl<const N: usize> Allocator for &InlineAllocator<N> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let base = self.base() as usize;
+    let align = layout.align();
+    let aligned_start = ((base + self.used.get() + align - 1) & !(align - 1)) - base;
+
+    let end = aligned_start.checked_add(layout.size()).ok_or(AllocError)?;
+    if end > N {
+      return Err(AllocError);
+    }
+
+    self.used.set(end);
+    let ptr = unsafe { NonNull::new_unchecked(self.base().add(aligned_start)) };
+    Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    if let Some(offset) = self.offset_of(ptr)
+      && offset + layout.size() == self.used.get()
+    {
+      self.used.set(offset);
+    }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let offset = self.offset_of(ptr).ok_or(AllocError)?;
+
+    if offset + old_layout.size() == self.used.get()
+      && (ptr.as_ptr() as usize).is_multiple_of(new_layout.align())
+      && offset + new_layout.size() <= N
+    {
+      self.used.set(offset + new_layout.size());
+      return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+    }
+
+    let new_ptr = self.allocate(new_layout)?;
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), old_layout.size());
+      self.deallocate(ptr, old_layout);
+    }
+    Ok(new_ptr)
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let offset = self.offset_of(ptr).ok_or(AllocError)?;
+
+    if !(ptr.as_ptr() as usize).is_multiple_of(new_layout.align()) {
+      let new_ptr = self.allocate(new_layout)?;
+      unsafe {
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), new_layout.size());
+        self.deallocate(ptr, old_layout);
+      }
+      return Ok(new_ptr);
+    }
+
+    if offset + old_layout.size() == self.used.get() {
+      self.used.set(offset + new_layout.size());
+    }
+    Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+  }
+}
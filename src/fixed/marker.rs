@@ -0,0 +1,81 @@
+//! Safe rewind points for [`FixedAllocator`].
+
+use core::ops::Deref;
+
+use super::FixedAllocator;
+use crate::observer::{
+  AllocObserver,
+  NoopObserver,
+};
+
+/// Saved position of a [`FixedAllocator`] that is restored when dropped.
+///
+/// The marker holds the allocator exclusively and hands it out again
+/// through [`Deref`], so every allocation made after the mark borrows the
+/// marker and must be gone before the rewind:
+///
+/// ```compile_fail
+/// #![feature(allocator_api)]
+/// use heaped::fixed::FixedAllocator;
+///
+/// let mut buffer = [0u8; 64];
+/// let mut allocator = FixedAllocator::new(&mut buffer);
+/// let escaped = {
+///   let marker = allocator.mark();
+///   Box::new_in(1u32, &*marker)
+/// };
+/// ```
+pub struct Marker<'a, 'fixed, O = NoopObserver>
+where
+  O: AllocObserver,
+{
+  /// Allocator to rewind.
+  alloc: &'a FixedAllocator<'fixed, O>,
+  /// Value of `used` when the marker was taken.
+  position: usize,
+}
+
+impl<'a, 'fixed, O> Marker<'a, 'fixed, O>
+where
+  O: AllocObserver,
+{
+  pub(super) fn new(alloc: &'a FixedAllocator<'fixed, O>) -> Self {
+    Self {
+      alloc,
+      position: alloc.used(),
+    }
+  }
+
+  /// Offset the allocator rewinds to.
+  pub fn position(&self) -> usize {
+    self.position
+  }
+
+  /// Take a nested marker that rewinds before this one.
+  pub fn mark(&mut self) -> Marker<'_, 'fixed, O> {
+    Marker::new(self.alloc)
+  }
+
+  /// Rewind now, releasing everything allocated since the mark.
+  pub fn rewind(self) {}
+}
+
+impl<'fixed, O> Deref for Marker<'_, 'fixed, O>
+where
+  O: AllocObserver,
+{
+  type Target = FixedAllocator<'fixed, O>;
+
+  fn deref(&self) -> &Self::Target {
+    self.alloc
+  }
+}
+
+impl<O> Drop for Marker<'_, '_, O>
+where
+  O: AllocObserver,
+{
+  fn drop(&mut self) {
+    unsafe { self.alloc.set_used(self.position) }
+  }
+}
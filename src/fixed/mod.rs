@@ -11,34 +11,127 @@ use core::{
   ptr::NonNull,
 };
 
+use crate::{
+  observer::{
+    AllocObserver,
+    NoopObserver,
+  },
+  owns::Owns,
+};
+
+mod double;
+mod frame;
+mod free_list;
+mod inline;
+mod marker;
+mod stack;
+
+pub use double::{
+  DoubleEndedAllocator,
+  HighEnd,
+  LowEnd,
+};
+pub use frame::{
+  FrameAllocator,
+  FrameRegion,
+};
+pub use free_list::{
+  FitPolicy,
+  FixedFreeListAllocator,
+};
+pub use inline::InlineAllocator;
+pub use marker::Marker;
+pub use stack::{
+  Frame,
+  StackAllocator,
+};
+
 #[derive(Debug)]
 struct FixedInner<'fixed> {
   mem: &'fixed mut [u8],
   used: usize,
   capacity: usize,
+  #[cfg(feature = "stats")]
+  stats: FixedStats,
+}
+
+#[cfg(feature = "stats")]
+#[derive(Debug)]
+/// Usage statistics, tracked only with the `stats` feature.
+struct FixedStats {
+  peak_used: usize,
+  allocations: usize,
+  failed_allocations: usize,
+  padding: usize,
+}
+
+#[cfg(feature = "stats")]
+impl FixedStats {
+  const fn new() -> Self {
+    Self {
+      peak_used: 0,
+      allocations: 0,
+      failed_allocations: 0,
+      padding: 0,
+    }
+  }
+}
+
+impl FixedInner<'_> {
+  /// Note that `used` may have moved up.
+  #[inline(always)]
+  fn record_used(&mut self) {
+    #[cfg(feature = "stats")]
+    {
+      self.stats.peak_used = self.stats.peak_used.max(self.used);
+    }
+  }
 }
 
 /// Allocator that hands out memory from a fixed slice.
-pub struct FixedAllocator<'fixed> {
+pub struct FixedAllocator<'fixed, O = NoopObserver>
+where
+  O: AllocObserver,
+{
   /// Interior mutable state tracking the buffer.
   inner: UnsafeCell<FixedInner<'fixed>>,
+  /// Observer notified about allocation events.
+  observer: O,
 }
 
 impl<'fixed> FixedAllocator<'fixed> {
   /// Create a new allocator from the given memory slice.
-  pub fn new(mem: &'fixed mut [u8]) -> Self {
+  pub const fn new(mem: &'fixed mut [u8]) -> Self {
+    Self::with_observer(mem, NoopObserver)
+  }
+}
+
+impl<'fixed, O> FixedAllocator<'fixed, O>
+where
+  O: AllocObserver,
+{
+  /// Create a new allocator that reports its activity to `observer`.
+  pub const fn with_observer(mem: &'fixed mut [u8], observer: O) -> Self {
     let capacity = mem.len();
     let inner = FixedInner {
       mem,
       used: 0,
       capacity,
+      #[cfg(feature = "stats")]
+      stats: FixedStats::new(),
     };
 
     Self {
       inner: UnsafeCell::new(inner),
+      observer,
     }
   }
 
+  /// The observer receiving this allocator's events.
+  pub fn observer(&self) -> &O {
+    &self.observer
+  }
+
   fn get(&self) -> &FixedInner<'fixed> {
     unsafe { &*self.inner.get() }
   }
@@ -63,6 +156,38 @@ impl<'fixed> FixedAllocator<'fixed> {
     inner.capacity - inner.used
   }
 
+  /// Highest value `used` has reached since creation or [`reset_stats`](Self::reset_stats).
+  #[cfg(feature = "stats")]
+  pub fn peak_used(&self) -> usize {
+    self.get().stats.peak_used
+  }
+
+  /// Number of successful allocations.
+  #[cfg(feature = "stats")]
+  pub fn allocation_count(&self) -> usize {
+    self.get().stats.allocations
+  }
+
+  /// Number of allocations that failed for lack of space.
+  #[cfg(feature = "stats")]
+  pub fn failed_allocation_count(&self) -> usize {
+    self.get().stats.failed_allocations
+  }
+
+  /// Total bytes skipped to satisfy alignment.
+  #[cfg(feature = "stats")]
+  pub fn bytes_lost_to_padding(&self) -> usize {
+    self.get().stats.padding
+  }
+
+  /// Clear the counters and restart peak tracking from the current usage.
+  #[cfg(feature = "stats")]
+  pub fn reset_stats(&self) {
+    let inner = self.get_mut();
+    inner.stats = FixedStats::new();
+    inner.stats.peak_used = inner.used;
+  }
+
   /// Reset the allocator, forgetting all previous allocations.
   ///
   /// # Safety
@@ -70,10 +195,60 @@ impl<'fixed> FixedAllocator<'fixed> {
   pub unsafe fn reset(&self) {
     let inner = self.get_mut();
     inner.used = 0;
+    self.observer.on_reset();
+  }
+
+  /// Remember the current position; dropping the marker rewinds to it.
+  pub fn mark(&mut self) -> Marker<'_, 'fixed, O> {
+    Marker::new(self)
+  }
+
+  /// Run `f` and release everything it allocated afterwards.
+  ///
+  /// Allocations made inside `f` borrow the allocator only for the call,
+  /// so they cannot escape the scope.
+  pub fn scope<R>(&mut self, f: impl FnOnce(&Self) -> R) -> R {
+    let marker = self.mark();
+    f(&marker)
+  }
+
+  /// Offset of `ptr` from the start of the buffer, if it lies inside it.
+  pub(crate) fn offset_of(&self, ptr: NonNull<u8>) -> Option<usize> {
+    let inner = self.get();
+    let ptr_addr = ptr.as_ptr() as usize;
+    let mem_start = inner.mem.as_ptr() as usize;
+
+    if ptr_addr < mem_start || ptr_addr >= mem_start + inner.capacity {
+      return None;
+    }
+    Some(ptr_addr - mem_start)
+  }
+
+  /// Move the bump pointer to `used`.
+  ///
+  /// # Safety
+  /// Memory past `used` must no longer be in use.
+  pub(crate) unsafe fn set_used(&self, used: usize) {
+    let inner = self.get_mut();
+    debug_assert!(used <= inner.capacity);
+    inner.used = used;
+    inner.record_used();
   }
 }
 
-unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
+impl<O> Owns for FixedAllocator<'_, O>
+where
+  O: AllocObserver,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.offset_of(ptr).is_some()
+  }
+}
+
+unsafe impl<'fixed, O> Allocator for FixedAllocator<'fixed, O>
+where
+  O: AllocObserver,
+{
   fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
     let inner = self.get_mut();
 
@@ -81,15 +256,30 @@ unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
     let align = layout.align();
     let size = layout.size();
 
-    let aligned_start = (start + align - 1) & !(align - 1);
+    let base = inner.mem.as_ptr() as usize;
+    let aligned_start = ((base + start + align - 1) & !(align - 1)) - base;
+
+    let end = match aligned_start.checked_add(size) {
+      Some(end) if end <= inner.capacity => end,
+      _ => {
+        #[cfg(feature = "stats")]
+        {
+          inner.stats.failed_allocations += 1;
+        }
+        return Err(AllocError);
+      }
+    };
 
-    if aligned_start + size > inner.capacity {
-      return Err(AllocError);
+    inner.used = end;
+    inner.record_used();
+    #[cfg(feature = "stats")]
+    {
+      inner.stats.allocations += 1;
+      inner.stats.padding += aligned_start - start;
     }
 
-    inner.used = aligned_start + size;
-
     let ptr = unsafe { NonNull::new_unchecked(inner.mem.as_mut_ptr().add(aligned_start)) };
+    self.observer.on_alloc(layout);
 
     Ok(NonNull::slice_from_raw_parts(ptr, size))
   }
@@ -110,6 +300,7 @@ unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
     if offset + size == inner.used {
       inner.used = offset;
     }
+    self.observer.on_dealloc(layout);
   }
 
   unsafe fn grow(
@@ -132,6 +323,8 @@ unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
     let new_size = new_layout.size();
 
     if new_size <= old_size {
+      self.observer.on_dealloc(old_layout);
+      self.observer.on_alloc(new_layout);
       return Ok(NonNull::slice_from_raw_parts(ptr, new_size));
     }
 
@@ -139,6 +332,9 @@ unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
       let additional_size = new_size - old_size;
       if inner.used + additional_size <= inner.capacity {
         inner.used += additional_size;
+        inner.record_used();
+        self.observer.on_dealloc(old_layout);
+        self.observer.on_alloc(new_layout);
         return Ok(NonNull::slice_from_raw_parts(ptr, new_size));
       }
     }
@@ -176,6 +372,8 @@ unsafe impl<'fixed> Allocator for FixedAllocator<'fixed> {
     if offset + old_size == inner.used {
       inner.used = offset + new_size;
     }
+    self.observer.on_dealloc(old_layout);
+    self.observer.on_alloc(new_layout);
 
     Ok(NonNull::slice_from_raw_parts(ptr, new_size))
   }
@@ -0,0 +1,354 @@
+//! Stack allocator with rewindable frames on top of [`FixedAllocator`].
+
+use alloc::alloc::{
+  AllocError,
+  Allocator,
+  Layout,
+};
+use core::{
+  cell::Cell,
+  ptr::{
+    self,
+    NonNull,
+  },
+};
+
+use super::FixedAllocator;
+use crate::owns::Owns;
+
+/// Header stored in front of every block.
+#[derive(Clone, Copy)]
+struct Header {
+  /// Stack top before the block was allocated.
+  previous: usize,
+  /// Header of the block allocated just before this one.
+  below: Option<NonNull<Header>>,
+  /// Position of the block in allocation order. A block moved by
+  /// [`resize_block`](StackAllocator::resize_block) keeps the position of
+  /// the original.
+  index: usize,
+  /// Whether the block was freed while other blocks sat on top of it.
+  freed: bool,
+}
+
+/// Layout of a header followed by `layout`, and the offset of the block.
+fn block_layout(layout: Layout) -> Result<(Layout, usize), AllocError> {
+  Layout::new::<Header>().extend(layout).map_err(|_| AllocError)
+}
+
+/// LIFO allocator over a fixed buffer with frame markers.
+///
+/// Every block remembers where the stack top was before it was allocated,
+/// so freeing blocks in reverse order gives all of their memory back,
+/// including alignment padding. Freeing a block out of order panics in debug
+/// builds and leaks the block until the enclosing frame is popped in release
+/// builds.
+///
+/// Growing a block that is not on top moves it to the top while keeping its
+/// place in allocation order. The old copy is reclaimed once every block
+/// above it has been freed.
+///
+/// [`push_frame`](Self::push_frame) returns a [`Frame`] that rewinds the
+/// stack when dropped. Allocations made through a frame borrow it, so they
+/// cannot outlive the rewind.
+pub struct StackAllocator<'fixed> {
+  /// Bump allocator owning the buffer.
+  fixed: FixedAllocator<'fixed>,
+  /// Header of the most recently allocated block still on the stack.
+  top: Cell<Option<NonNull<Header>>>,
+  /// Stack top of the innermost open frame, below which freed blocks are
+  /// left for the frame to reclaim.
+  floor: Cell<usize>,
+  /// Number of live blocks.
+  live: Cell<usize>,
+  /// Number of live blocks when the innermost open frame was opened.
+  base: Cell<usize>,
+}
+
+// SAFETY: The header pointers only point into the buffer the allocator
+// borrows exclusively, so moving it to another thread moves them along.
+unsafe impl Send for StackAllocator<'_> {}
+
+impl<'fixed> StackAllocator<'fixed> {
+  /// Create a new stack allocator from the given memory slice.
+  pub const fn new(mem: &'fixed mut [u8]) -> Self {
+    Self {
+      fixed: FixedAllocator::new(mem),
+      top: Cell::new(None),
+      floor: Cell::new(0),
+      live: Cell::new(0),
+      base: Cell::new(0),
+    }
+  }
+
+  /// Total capacity of the underlying buffer.
+  pub fn capacity(&self) -> usize {
+    self.fixed.capacity()
+  }
+
+  /// Amount of memory already allocated.
+  pub fn used(&self) -> usize {
+    self.fixed.used()
+  }
+
+  /// Remaining capacity in bytes.
+  pub fn available(&self) -> usize {
+    self.fixed.available()
+  }
+
+  /// Discard every allocation.
+  pub fn reset(&mut self) {
+    unsafe { self.fixed.reset() }
+    self.top.set(None);
+    self.live.set(0);
+  }
+
+  /// Open a frame that releases everything allocated through it when dropped.
+  pub fn push_frame(&mut self) -> Frame<'_, 'fixed> {
+    Frame::new(self)
+  }
+
+  fn allocate_block(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    let index = self.live.get();
+    let ptr = self.place_block(layout, index)?;
+    self.live.set(index + 1);
+    Ok(ptr)
+  }
+
+  /// Push a block holding position `index` in allocation order.
+  fn place_block(&self, layout: Layout, index: usize) -> Result<NonNull<[u8]>, AllocError> {
+    let (block, offset) = block_layout(layout)?;
+    let previous = self.fixed.used();
+    let header = self.fixed.allocate(block)?.cast::<Header>();
+
+    unsafe {
+      header.write(Header {
+        previous,
+        below: self.top.replace(Some(header)),
+        index,
+        freed: false,
+      });
+      let ptr = header.cast::<u8>().add(offset);
+      Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+  }
+
+  /// Offsets of the header and the end of the block at `ptr`, if it lies in
+  /// the buffer.
+  fn block_bounds(&self, ptr: NonNull<u8>, layout: Layout) -> Option<(usize, usize)> {
+    let (block, offset) = block_layout(layout).ok()?;
+    let header = self.fixed.offset_of(ptr)?.checked_sub(offset)?;
+    Some((header, header + block.size()))
+  }
+
+  unsafe fn deallocate_block(&self, ptr: NonNull<u8>, layout: Layout) {
+    if self.fixed.offset_of(ptr).is_none() {
+      return;
+    }
+
+    let (_, offset) = block_layout(layout).unwrap();
+    let header = unsafe { ptr.sub(offset).cast::<Header>() };
+    let index = unsafe { (*header.as_ptr()).index };
+    let in_order = index + 1 == self.live.get() && index >= self.base.get();
+    debug_assert!(
+      in_order,
+      "StackAllocator blocks must be freed in reverse allocation order"
+    );
+    if !in_order {
+      return;
+    }
+
+    self.live.set(index);
+    unsafe { self.release(header) }
+  }
+
+  /// Mark the block at `header` as freed and pop whatever that uncovers.
+  unsafe fn release(&self, header: NonNull<Header>) {
+    unsafe {
+      (*header.as_ptr()).freed = true;
+      self.unwind();
+    }
+  }
+
+  /// Pop freed blocks off the top of the stack, down to the current frame.
+  unsafe fn unwind(&self) {
+    while let Some(header) = self.top.get() {
+      let Header {
+        previous,
+        below,
+        freed,
+        ..
+      } = unsafe { header.read() };
+      if !freed || previous < self.floor.get() {
+        break;
+      }
+      unsafe { self.fixed.set_used(previous) };
+      self.top.set(below);
+    }
+  }
+
+  unsafe fn resize_block(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    let (_, old_offset) = block_layout(old_layout)?;
+    let (new_block, new_offset) = block_layout(new_layout)?;
+
+    if old_offset == new_offset && (ptr.as_ptr() as usize).is_multiple_of(new_layout.align()) {
+      let top = self
+        .block_bounds(ptr, old_layout)
+        .filter(|&(_, end)| end == self.fixed.used());
+      if let Some((header, _)) = top {
+        if let Some(new_end) = header.checked_add(new_block.size())
+          && new_end <= self.capacity()
+        {
+          unsafe { self.fixed.set_used(new_end) };
+          return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+      } else if new_layout.size() <= old_layout.size() {
+        return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+      }
+    }
+
+    // The moved block takes over the old one's place in allocation order,
+    // so releasing the old copy is exempt from the ordering check.
+    let header = unsafe { ptr.sub(old_offset).cast::<Header>() };
+    let new_ptr = self.place_block(new_layout, unsafe { (*header.as_ptr()).index })?;
+    let len = old_layout.size().min(new_layout.size());
+    unsafe {
+      ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr().cast::<u8>(), len);
+      self.release(header);
+    }
+    Ok(new_ptr)
+  }
+}
+
+impl Owns for StackAllocator<'_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.fixed.owns(ptr)
+  }
+}
+
+unsafe impl Allocator for StackAllocator<'_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    self.allocate_block(layout)
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    unsafe { self.deallocate_block(ptr, layout) }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.resize_block(ptr, old_layout, new_layout) }
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.resize_block(ptr, old_layout, new_layout) }
+  }
+}
+
+/// A scope on a [`StackAllocator`] that rewinds it when dropped.
+///
+/// Frames nest through [`Frame::push_frame`]; the borrow checker makes sure
+/// inner frames are dropped before outer ones.
+pub struct Frame<'stack, 'fixed> {
+  /// Stack the frame allocates from.
+  stack: &'stack StackAllocator<'fixed>,
+  /// Stack top when the frame was opened.
+  marker: usize,
+  /// Top block when the frame was opened.
+  top: Option<NonNull<Header>>,
+  /// Floor of the enclosing frame.
+  floor: usize,
+  /// Number of live blocks when the frame was opened.
+  live: usize,
+  /// Base of the enclosing frame.
+  base: usize,
+}
+
+impl<'stack, 'fixed> Frame<'stack, 'fixed> {
+  fn new(stack: &'stack StackAllocator<'fixed>) -> Self {
+    let marker = stack.used();
+    let live = stack.live.get();
+    Self {
+      stack,
+      marker,
+      top: stack.top.get(),
+      floor: stack.floor.replace(marker),
+      live,
+      base: stack.base.replace(live),
+    }
+  }
+
+  /// Stack top the frame rewinds to.
+  pub fn marker(&self) -> usize {
+    self.marker
+  }
+
+  /// Bytes allocated since the frame was opened.
+  pub fn used(&self) -> usize {
+    self.stack.used() - self.marker
+  }
+
+  /// Open a nested frame.
+  pub fn push_frame(&mut self) -> Frame<'_, 'fixed> {
+    Frame::new(self.stack)
+  }
+}
+
+impl Drop for Frame<'_, '_> {
+  fn drop(&mut self) {
+    unsafe { self.stack.fixed.set_used(self.marker) };
+    self.stack.top.set(self.top);
+    self.stack.floor.set(self.floor);
+    self.stack.live.set(self.live);
+    self.stack.base.set(self.base);
+    unsafe { self.stack.unwind() };
+  }
+}
+
+impl Owns for Frame<'_, '_> {
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.stack.owns(ptr)
+  }
+}
+
+unsafe impl Allocator for Frame<'_, '_> {
+  fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    self.stack.allocate_block(layout)
+  }
+
+  unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    unsafe { self.stack.deallocate_block(ptr, layout) }
+  }
+
+  unsafe fn grow(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.stack.resize_block(ptr, old_layout, new_layout) }
+  }
+
+  unsafe fn shrink(
+    &self,
+    ptr: NonNull<u8>,
+    old_layout: Layout,
+    new_layout: Layout,
+  ) -> Result<NonNull<[u8]>, AllocError> {
+    unsafe { self.stack.resize_block(ptr, old_layout, new_layout) }
+  }
+}
@@ -1,4 +1,5 @@
 use super::FixedAllocator;
+use crate::observer::testing::CountingObserver;
 use alloc::alloc::{
   Allocator,
   Layout,
@@ -144,3 +145,589 @@ fn grow_with_relocation() {
     assert_eq!(new_ptr.as_ptr().cast::<u8>().read(), 42);
   }
 }
+
+#[test]
+fn observer_events() {
+  let mut buffer = [0u8; 64];
+  let observer = CountingObserver::default();
+  let allocator = FixedAllocator::with_observer(&mut buffer, &observer);
+
+  let layout = Layout::new::<u64>();
+  let ptr = allocator.allocate(layout).unwrap();
+  assert!(allocator.allocate(Layout::from_size_align(128, 1).unwrap()).is_err());
+  assert_eq!(observer.allocs.get(), 1);
+
+  unsafe {
+    allocator.deallocate(ptr.cast(), layout);
+  }
+  assert_eq!(observer.deallocs.get(), 1);
+
+  unsafe {
+    allocator.reset();
+  }
+  assert_eq!(observer.resets.get(), 1);
+}
+
+#[test]
+fn stack_lifo_reclaims_padding() {
+  use super::StackAllocator;
+
+  let mut buffer = [0u8; 1024];
+  let stack = StackAllocator::new(&mut buffer);
+
+  let small = Layout::new::<u8>();
+  let wide = Layout::from_size_align(16, 64).unwrap();
+  let a = stack.allocate(small).unwrap();
+  let b = stack.allocate(wide).unwrap();
+  assert_eq!(b.cast::<u8>().as_ptr() as usize % 64, 0);
+
+  unsafe {
+    stack.deallocate(b.cast(), wide);
+    stack.deallocate(a.cast(), small);
+  }
+  assert_eq!(stack.used(), 0);
+}
+
+#[test]
+fn stack_frames_rewind() {
+  use super::StackAllocator;
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 4096];
+  let mut stack = StackAllocator::new(&mut buffer);
+
+  {
+    let mut outer = stack.push_frame();
+    let mut first = Vec::new_in(&outer);
+    first.extend_from_slice(&[1u32, 2, 3]);
+    core::mem::forget(first);
+    let outer_used = outer.used();
+    assert!(outer_used > 0);
+
+    {
+      let inner = outer.push_frame();
+      let mut scratch = Vec::with_capacity_in(64, &inner);
+      scratch.extend(0..64u64);
+      assert_eq!(scratch.iter().sum::<u64>(), 2016);
+      core::mem::forget(scratch);
+    }
+    assert_eq!(outer.used(), outer_used);
+  }
+
+  assert_eq!(stack.used(), 0);
+}
+
+#[test]
+fn stack_vec_grows_in_place() {
+  use super::StackAllocator;
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 4096];
+  let stack = StackAllocator::new(&mut buffer);
+
+  let mut values = Vec::new_in(&stack);
+  values.push(0u32);
+  let start = values.as_ptr();
+  for i in 1..256 {
+    values.push(i);
+  }
+  assert_eq!(values.as_ptr(), start);
+
+  values.shrink_to_fit();
+  drop(values);
+  assert_eq!(stack.used(), 0);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "reverse allocation order")]
+fn stack_out_of_order_free_panics() {
+  use super::StackAllocator;
+
+  let mut buffer = [0u8; 256];
+  let stack = StackAllocator::new(&mut buffer);
+
+  let layout = Layout::new::<u64>();
+  let a = stack.allocate(layout).unwrap();
+  let _b = stack.allocate(layout).unwrap();
+  unsafe {
+    stack.deallocate(a.cast(), layout);
+  }
+}
+
+#[test]
+fn stack_vec_relocation_reclaims_old_block() {
+  use super::StackAllocator;
+  use alloc::{
+    boxed::Box,
+    vec::Vec,
+  };
+
+  let mut buffer = [0u8; 4096];
+  let stack = StackAllocator::new(&mut buffer);
+
+  let mut values = Vec::new_in(&stack);
+  values.push(0u32);
+  let other = Box::new_in(1u64, &stack);
+  let start = values.as_ptr();
+  values.extend(1..64);
+  assert_ne!(values.as_ptr(), start);
+
+  drop(other);
+  drop(values);
+  assert_eq!(stack.used(), 0);
+}
+
+#[test]
+fn stack_frame_relocation_in_order() {
+  use super::StackAllocator;
+  use alloc::{
+    boxed::Box,
+    vec::Vec,
+  };
+
+  let mut buffer = [0u8; 4096];
+  let mut stack = StackAllocator::new(&mut buffer);
+  let layout = Layout::new::<u64>();
+  let below = stack.allocate(layout).unwrap();
+
+  {
+    let frame = stack.push_frame();
+    let mut values = Vec::new_in(&frame);
+    values.push(0u16);
+    let first = Box::new_in(1u64, &frame);
+    let second = Box::new_in(2u64, &frame);
+    values.extend(1..200);
+    assert!(values.iter().copied().eq(0..200));
+
+    drop(second);
+    drop(first);
+    drop(values);
+    assert_eq!(frame.used(), 0);
+  }
+
+  unsafe { stack.deallocate(below.cast(), layout) };
+  assert_eq!(stack.used(), 0);
+}
+
+#[test]
+fn double_ended_basic() {
+  use super::DoubleEndedAllocator;
+
+  let mut buffer = [0u8; 256];
+  let allocator = DoubleEndedAllocator::new(&mut buffer);
+  let low = allocator.low();
+  let high = allocator.high();
+
+  let layout = Layout::new::<u64>();
+  let a = low.allocate(layout).unwrap();
+  let b = high.allocate(layout).unwrap();
+  assert_eq!(allocator.low_used(), 8);
+  assert!(allocator.high_used() >= 8);
+  assert!(a.cast::<u8>().as_ptr() < b.cast::<u8>().as_ptr());
+  assert_eq!(b.cast::<u8>().as_ptr() as usize % 8, 0);
+
+  unsafe {
+    high.deallocate(b.cast(), layout);
+  }
+  assert_eq!(allocator.high_used(), 0);
+  assert_eq!(allocator.available(), 256 - 8);
+}
+
+#[test]
+fn double_ended_ends_meet() {
+  use super::DoubleEndedAllocator;
+
+  let mut buffer = [0u8; 128];
+  let allocator = DoubleEndedAllocator::new(&mut buffer);
+
+  let chunk = Layout::from_size_align(48, 1).unwrap();
+  allocator.low().allocate(chunk).unwrap();
+  allocator.high().allocate(chunk).unwrap();
+  assert!(allocator.low().allocate(chunk).is_err());
+  assert!(allocator.high().allocate(chunk).is_err());
+  assert!(allocator.high().allocate(Layout::from_size_align(usize::MAX / 2, 1).unwrap()).is_err());
+
+  let rest = Layout::from_size_align(allocator.available(), 1).unwrap();
+  allocator.high().allocate(rest).unwrap();
+  assert_eq!(allocator.available(), 0);
+  assert!(allocator.low().allocate(Layout::new::<u8>()).is_err());
+}
+
+#[test]
+fn double_ended_high_grow_in_place() {
+  use super::DoubleEndedAllocator;
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 4096];
+  let allocator = DoubleEndedAllocator::new(&mut buffer);
+
+  let mut values = Vec::new_in(allocator.high());
+  for i in 0..512u32 {
+    values.push(i);
+    assert!(allocator.high_used() <= values.capacity() * 4 + 2 * size_of::<usize>() + 4);
+  }
+  assert!(values.iter().copied().eq(0..512));
+
+  drop(values);
+  assert_eq!(allocator.high_used(), 0);
+}
+
+#[test]
+fn double_ended_high_over_aligned_free() {
+  use super::DoubleEndedAllocator;
+
+  let mut buffer = [0u8; 512];
+  let allocator = DoubleEndedAllocator::new(&mut buffer);
+  let high = allocator.high();
+
+  let small = Layout::from_size_align(3, 1).unwrap();
+  let aligned = Layout::from_size_align(16, 64).unwrap();
+  let a = high.allocate(small).unwrap();
+  let b = high.allocate(aligned).unwrap();
+  assert_eq!(b.cast::<u8>().as_ptr() as usize % 64, 0);
+  assert!(allocator.high_used() >= 3 + 16);
+
+  unsafe {
+    high.deallocate(b.cast(), aligned);
+  }
+  assert_eq!(allocator.high_used(), 3);
+
+  unsafe {
+    high.deallocate(a.cast(), small);
+  }
+  assert_eq!(allocator.high_used(), 0);
+  assert_eq!(allocator.available(), 512);
+}
+
+#[test]
+fn double_ended_reset_high() {
+  use super::DoubleEndedAllocator;
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 1024];
+  let allocator = DoubleEndedAllocator::new(&mut buffer);
+
+  let mut persistent = Vec::new_in(allocator.low());
+  persistent.extend_from_slice(&[1u32, 2, 3, 4]);
+
+  for frame in 0..10u32 {
+    let mut scratch = Vec::with_capacity_in(32, allocator.high());
+    scratch.extend(0..32u32);
+    scratch.push(frame);
+    assert_eq!(scratch.len(), 33);
+    drop(scratch);
+    unsafe { allocator.reset_high() };
+    assert_eq!(allocator.high_used(), 0);
+  }
+
+  assert_eq!(persistent, [1, 2, 3, 4]);
+}
+
+#[test]
+fn free_list_reuses_holes() {
+  use super::FixedFreeListAllocator;
+
+  let mut buffer = [0u8; 1024];
+  let allocator = FixedFreeListAllocator::new(&mut buffer);
+  let capacity = allocator.capacity();
+
+  let layout = Layout::from_size_align(32, 8).unwrap();
+  let a = allocator.allocate(layout).unwrap();
+  let b = allocator.allocate(layout).unwrap();
+  let c = allocator.allocate(layout).unwrap();
+  assert_eq!(allocator.used(), 96);
+
+  unsafe {
+    allocator.deallocate(b.cast(), layout);
+  }
+  assert_eq!(allocator.used(), 64);
+  assert_eq!(allocator.free_blocks(), 2);
+
+  let d = allocator.allocate(layout).unwrap();
+  assert_eq!(d.cast::<u8>(), b.cast::<u8>());
+
+  unsafe {
+    allocator.deallocate(a.cast(), layout);
+    allocator.deallocate(c.cast(), layout);
+    allocator.deallocate(d.cast(), layout);
+  }
+  assert_eq!(allocator.used(), 0);
+  assert_eq!(allocator.free_blocks(), 1);
+  assert_eq!(allocator.largest_free_block(), capacity);
+}
+
+#[test]
+fn free_list_best_fit() {
+  use super::{
+    FitPolicy,
+    FixedFreeListAllocator,
+  };
+
+  let mut buffer = [0u8; 1024];
+  let allocator = FixedFreeListAllocator::with_policy(&mut buffer, FitPolicy::BestFit);
+
+  let big = Layout::from_size_align(128, 8).unwrap();
+  let small = Layout::from_size_align(32, 8).unwrap();
+  let a = allocator.allocate(big).unwrap();
+  let _guard1 = allocator.allocate(small).unwrap();
+  let b = allocator.allocate(small).unwrap();
+  let _guard2 = allocator.allocate(small).unwrap();
+
+  unsafe {
+    allocator.deallocate(a.cast(), big);
+    allocator.deallocate(b.cast(), small);
+  }
+
+  let c = allocator.allocate(small).unwrap();
+  assert_eq!(c.cast::<u8>(), b.cast::<u8>());
+}
+
+#[test]
+fn free_list_alignment_and_grow() {
+  use super::FixedFreeListAllocator;
+  use alloc::{
+    boxed::Box,
+    vec::Vec,
+  };
+
+  let mut buffer = [0u8; 4096];
+  let allocator = FixedFreeListAllocator::new(&mut buffer);
+
+  let wide = Layout::from_size_align(8, 256).unwrap();
+  let aligned = allocator.allocate(wide).unwrap();
+  assert_eq!(aligned.cast::<u8>().as_ptr() as usize % 256, 0);
+  unsafe {
+    allocator.deallocate(aligned.cast(), wide);
+  }
+  assert_eq!(allocator.used(), 0);
+  assert_eq!(allocator.free_blocks(), 1);
+
+  for round in 0..50u64 {
+    let mut values = Vec::new_in(&allocator);
+    let boxed = Box::new_in(round, &allocator);
+    values.extend(0..100u64);
+    values.shrink_to(10);
+    assert_eq!(*boxed, round);
+    assert_eq!(values.len(), 100);
+  }
+  assert_eq!(allocator.used(), 0);
+  assert_eq!(allocator.free_blocks(), 1);
+}
+
+#[test]
+fn free_list_exhaustion() {
+  use super::FixedFreeListAllocator;
+
+  let mut buffer = [0u8; 256];
+  let allocator = FixedFreeListAllocator::new(&mut buffer);
+
+  let layout = Layout::from_size_align(64, 8).unwrap();
+  while allocator.allocate(layout).is_ok() {}
+  assert!(allocator.available() < 64);
+  assert!(allocator.allocate(Layout::from_size_align(usize::MAX / 2, 1).unwrap()).is_err());
+
+  let mut tiny = [0u8; 8];
+  let empty = FixedFreeListAllocator::new(&mut tiny);
+  assert!(empty.allocate(Layout::new::<u8>()).is_err());
+}
+
+#[test]
+fn inline_allocator_basic() {
+  use super::InlineAllocator;
+  use alloc::vec::Vec;
+
+  struct Scratch {
+    alloc: InlineAllocator<256>,
+  }
+
+  let scratch = Scratch {
+    alloc: InlineAllocator::new(),
+  };
+  assert_eq!(scratch.alloc.capacity(), 256);
+
+  {
+    let mut values = Vec::new_in(&scratch.alloc);
+    values.extend_from_slice(&[1u64, 2, 3, 4]);
+    assert_eq!(values.iter().sum::<u64>(), 10);
+    assert!(scratch.alloc.used() >= 32);
+  }
+  assert_eq!(scratch.alloc.used(), 0);
+
+  let layout = Layout::from_size_align(16, 64).unwrap();
+  let ptr = (&scratch.alloc).allocate(layout).unwrap();
+  assert_eq!(ptr.cast::<u8>().as_ptr() as usize % 64, 0);
+  assert!((&scratch.alloc).allocate(Layout::from_size_align(512, 1).unwrap()).is_err());
+
+  unsafe {
+    scratch.alloc.reset();
+  }
+  assert_eq!(scratch.alloc.available(), 256);
+}
+
+#[test]
+fn owns_queries() {
+  use super::{
+    DoubleEndedAllocator,
+    FixedFreeListAllocator,
+    InlineAllocator,
+  };
+  use crate::owns::Owns;
+  use core::ptr::NonNull;
+
+  let outside = 0u64;
+  let outside = NonNull::from(&outside).cast::<u8>();
+  let layout = Layout::new::<u64>();
+
+  let mut buffer = [0u8; 64];
+  let fixed = FixedAllocator::new(&mut buffer);
+  let ptr = fixed.allocate(layout).unwrap().cast::<u8>();
+  assert!(fixed.owns(ptr) && !fixed.owns(outside));
+
+  let mut buffer = [0u8; 64];
+  let free_list = FixedFreeListAllocator::new(&mut buffer);
+  let ptr = free_list.allocate(layout).unwrap().cast::<u8>();
+  assert!(free_list.owns(ptr) && !free_list.owns(outside));
+
+  let inline = InlineAllocator::<64>::new();
+  let ptr = (&inline).allocate(layout).unwrap().cast::<u8>();
+  assert!(inline.owns(ptr) && !inline.owns(outside));
+
+  let mut buffer = [0u8; 64];
+  let double = DoubleEndedAllocator::new(&mut buffer);
+  let low = double.low().allocate(layout).unwrap().cast::<u8>();
+  let high = double.high().allocate(layout).unwrap().cast::<u8>();
+  assert!(double.owns(low) && double.owns(high));
+  assert!(double.low().owns(low) && !double.low().owns(high));
+  assert!(double.high().owns(high) && !double.high().owns(low));
+}
+
+#[test]
+fn marker_rewinds() {
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 1024];
+  let mut allocator = FixedAllocator::new(&mut buffer);
+
+  let _persistent = allocator.allocate(Layout::new::<u64>()).unwrap();
+  {
+    let mut marker = allocator.mark();
+    assert_eq!(marker.position(), 8);
+
+    let mut values = Vec::new_in(&*marker);
+    values.extend(0..16u32);
+    assert!(marker.used() > 8);
+    drop(values);
+
+    {
+      let inner = marker.mark();
+      inner.allocate(Layout::new::<[u8; 100]>()).unwrap();
+      assert!(inner.used() >= 108);
+    }
+    assert_eq!(marker.used(), 8);
+
+    marker.allocate(Layout::new::<u32>()).unwrap();
+    marker.rewind();
+  }
+  assert_eq!(allocator.used(), 8);
+}
+
+#[test]
+fn scope_rewinds() {
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 1024];
+  let mut allocator = FixedAllocator::new(&mut buffer);
+
+  for request in 0..100u32 {
+    let total = allocator.scope(|alloc| {
+      let mut values = Vec::new_in(alloc);
+      values.extend(0..=request);
+      values.iter().sum::<u32>()
+    });
+    assert_eq!(total, request * (request + 1) / 2);
+    assert_eq!(allocator.used(), 0);
+  }
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn stats_tracking() {
+  let mut buffer = [0u8; 64];
+  let allocator = FixedAllocator::new(&mut buffer);
+
+  let byte = Layout::new::<u8>();
+  let word = Layout::new::<u64>();
+  let _a = allocator.allocate(byte).unwrap();
+  let b = allocator.allocate(word).unwrap();
+  assert!(allocator.allocate(Layout::from_size_align(64, 1).unwrap()).is_err());
+
+  assert_eq!(allocator.allocation_count(), 2);
+  assert_eq!(allocator.failed_allocation_count(), 1);
+  assert_eq!(allocator.peak_used(), allocator.used());
+  assert_eq!(
+    allocator.bytes_lost_to_padding(),
+    allocator.used() - byte.size() - word.size()
+  );
+
+  let peak = allocator.peak_used();
+  unsafe {
+    allocator.deallocate(b.cast(), word);
+  }
+  assert!(allocator.used() < peak);
+  assert_eq!(allocator.peak_used(), peak);
+
+  allocator.reset_stats();
+  assert_eq!(allocator.peak_used(), allocator.used());
+  assert_eq!(allocator.allocation_count(), 0);
+  assert_eq!(allocator.failed_allocation_count(), 0);
+  assert_eq!(allocator.bytes_lost_to_padding(), 0);
+}
+
+#[test]
+fn frame_allocator_rotation() {
+  use super::FrameAllocator;
+  use alloc::vec::Vec;
+
+  let mut buffer = [0u8; 512];
+  let frames = FrameAllocator::<2>::new(&mut buffer);
+  assert_eq!(frames.frame_capacity(), 256);
+
+  let first = frames.next_frame();
+  let mut old = Vec::new_in(&first);
+  old.extend_from_slice(&[1u32, 2, 3]);
+
+  let second = frames.next_frame();
+  let mut new = Vec::new_in(&second);
+  new.extend(old.iter().map(|x| x * 10));
+  assert!(frames.try_next_frame().is_none());
+
+  drop(old);
+  drop(first);
+  let third = frames.next_frame();
+  assert_eq!(third.number(), 2);
+  assert_eq!(third.used(), 0);
+  assert_eq!(new, [10, 20, 30]);
+  assert_eq!(frames.frame_count(), 3);
+}
+
+#[test]
+fn frame_allocator_triple_buffered() {
+  use super::FrameAllocator;
+
+  let mut buffer = [0u8; 300];
+  let frames = FrameAllocator::<3>::new(&mut buffer);
+  let layout = Layout::new::<[u8; 64]>();
+
+  let mut window = alloc::collections::VecDeque::new();
+  for number in 0..10 {
+    if window.len() == 3 {
+      window.pop_front();
+    }
+    let frame = frames.next_frame();
+    assert_eq!(frame.number(), number);
+    assert_eq!(frame.used(), 0);
+    frame.allocate(layout).unwrap();
+    window.push_back(frame);
+  }
+}
@@ -0,0 +1,219 @@
+//! Adapter for installing heaped allocators as the `#[global_allocator]`.
+//!
+//! Available with the `allocator` feature.
+
+use alloc::alloc::{
+  Allocator,
+  GlobalAlloc,
+  Layout,
+};
+use core::{
+  cell::UnsafeCell,
+  hint,
+  ptr::{
+    self,
+    NonNull,
+  },
+  sync::atomic::{
+    AtomicBool,
+    Ordering,
+  },
+};
+
+use crate::owns::Owns;
+
+/// Minimal spin lock guarding the wrapped allocator.
+struct SpinLock<T> {
+  /// Whether the lock is currently held.
+  locked: AtomicBool,
+  /// The protected value.
+  value: UnsafeCell<T>,
+  /// Identity of the thread holding the lock, or zero.
+  #[cfg(debug_assertions)]
+  owner: core::sync::atomic::AtomicUsize,
+}
+
+/// A per-thread address, used to tell which thread holds a lock.
+#[cfg(debug_assertions)]
+fn thread_id() -> usize {
+  #[thread_local]
+  static MARKER: u8 = 0;
+  &raw const MARKER as usize
+}
+
+impl<T> SpinLock<T> {
+  const fn new(value: T) -> Self {
+    Self {
+      locked: AtomicBool::new(false),
+      value: UnsafeCell::new(value),
+      #[cfg(debug_assertions)]
+      owner: core::sync::atomic::AtomicUsize::new(0),
+    }
+  }
+
+  fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+    // Only the current thread ever stores its own identity, so seeing it
+    // here means the lock is already held further up this thread's stack.
+    #[cfg(debug_assertions)]
+    assert!(
+      self.owner.load(Ordering::Relaxed) != thread_id(),
+      "GlobalAdapter::with re-entered on the same thread"
+    );
+
+    while self
+      .locked
+      .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+      .is_err()
+    {
+      while self.locked.load(Ordering::Relaxed) {
+        hint::spin_loop();
+      }
+    }
+
+    #[cfg(debug_assertions)]
+    self.owner.store(thread_id(), Ordering::Relaxed);
+    let _guard = Unlock(self);
+    f(unsafe { &*self.value.get() })
+  }
+}
+
+/// Releases the spin lock when dropped, even if the closure panics.
+struct Unlock<'a, T>(&'a SpinLock<T>);
+
+impl<T> Drop for Unlock<'_, T> {
+  fn drop(&mut self) {
+    #[cfg(debug_assertions)]
+    self.0.owner.store(0, Ordering::Relaxed);
+    self.0.locked.store(false, Ordering::Release);
+  }
+}
+
+/// Wraps an [`Allocator`] so it can be used as a [`GlobalAlloc`].
+///
+/// Every call is serialized through a spin lock, which makes allocators with
+/// interior mutability such as [`FixedAllocator`](crate::fixed::FixedAllocator)
+/// safe to share between threads.
+///
+/// ```no_run
+/// use heaped::{
+///   fixed::FixedAllocator,
+///   global::GlobalAdapter,
+/// };
+///
+/// static mut HEAP: [u8; 64 * 1024] = [0; 64 * 1024];
+///
+/// #[global_allocator]
+/// static GLOBAL: GlobalAdapter<FixedAllocator<'static>> =
+///   GlobalAdapter::new(FixedAllocator::new(unsafe {
+///     core::slice::from_raw_parts_mut((&raw mut HEAP).cast::<u8>(), 64 * 1024)
+///   }));
+/// # fn main() {}
+/// ```
+pub struct GlobalAdapter<A>
+where
+  A: Allocator,
+{
+  /// The wrapped allocator.
+  inner: SpinLock<A>,
+}
+
+impl<A> GlobalAdapter<A>
+where
+  A: Allocator,
+{
+  /// Wrap `allocator` for use as a global allocator.
+  pub const fn new(allocator: A) -> Self {
+    Self {
+      inner: SpinLock::new(allocator),
+    }
+  }
+
+  /// Run `f` with exclusive access to the wrapped allocator.
+  ///
+  /// # Deadlocks
+  ///
+  /// The lock is held while `f` runs and is not re-entrant. If this adapter
+  /// is the `#[global_allocator]`, `f` must not allocate or free through
+  /// the global allocator, and must not call `with` again, or the thread
+  /// spins forever. Debug builds detect this and panic instead.
+  pub fn with<R>(&self, f: impl FnOnce(&A) -> R) -> R {
+    self.inner.with(f)
+  }
+}
+
+impl<A> Owns for GlobalAdapter<A>
+where
+  A: Allocator + Owns,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    self.with(|allocator| allocator.owns(ptr))
+  }
+}
+
+// SAFETY: All access to the wrapped allocator goes through the spin lock, so
+// it is only ever used from one thread at a time.
+unsafe impl<A> Sync for GlobalAdapter<A> where A: Allocator + Send {}
+
+unsafe impl<A> GlobalAlloc for GlobalAdapter<A>
+where
+  A: Allocator,
+{
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    self.with(|allocator| match allocator.allocate(layout) {
+      Ok(ptr) => ptr.as_ptr().cast::<u8>(),
+      Err(_) => ptr::null_mut(),
+    })
+  }
+
+  unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+    self.with(|allocator| match allocator.allocate_zeroed(layout) {
+      Ok(ptr) => ptr.as_ptr().cast::<u8>(),
+      Err(_) => ptr::null_mut(),
+    })
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    let ptr = unsafe { NonNull::new_unchecked(ptr) };
+    self.with(|allocator| unsafe { allocator.deallocate(ptr, layout) })
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    let ptr = unsafe { NonNull::new_unchecked(ptr) };
+    let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+
+    self.with(|allocator| {
+      let result = if new_size >= layout.size() {
+        unsafe { allocator.grow(ptr, layout, new_layout) }
+      } else {
+        unsafe { allocator.shrink(ptr, layout, new_layout) }
+      };
+      match result {
+        Ok(ptr) => ptr.as_ptr().cast::<u8>(),
+        Err(_) => ptr::null_mut(),
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::fixed::FixedAllocator;
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "re-entered")]
+  fn with_reentry_panics() {
+    let mut buffer = [0u8; 64];
+    let adapter = GlobalAdapter::new(FixedAllocator::new(&mut buffer));
+    adapter.with(|_| adapter.with(|allocator| allocator.used()));
+  }
+
+  #[test]
+  fn with_after_release() {
+    let mut buffer = [0u8; 64];
+    let adapter = GlobalAdapter::new(FixedAllocator::new(&mut buffer));
+    unsafe { adapter.dealloc(adapter.alloc(Layout::new::<u32>()), Layout::new::<u32>()) };
+    assert_eq!(adapter.with(|allocator| allocator.used()), 0);
+  }
+}
@@ -7,11 +7,17 @@
 
 #![feature(allocator_api)]
 #![feature(dropck_eyepatch)]
+#![cfg_attr(all(feature = "allocator", debug_assertions), feature(thread_local))]
 #![allow(clippy::module_inception, clippy::mut_from_ref)]
 
 extern crate alloc;
 
 pub mod arena;
 pub mod bitmap;
+pub mod fallback;
 pub mod fixed;
+#[cfg(feature = "allocator")]
+pub mod global;
+pub mod observer;
 pub mod once;
+pub mod owns;
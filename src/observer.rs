@@ -0,0 +1,97 @@
+//! Hooks for observing allocator and arena activity.
+
+use alloc::alloc::Layout;
+
+/// Receives notifications about allocations made by an allocator or arena.
+///
+/// Every method has an empty default implementation, so observers only
+/// override the events they care about. Callbacks take `&self`; observers
+/// that record state use interior mutability.
+pub trait AllocObserver {
+  /// A block described by `layout` was handed out.
+  fn on_alloc(&self, _layout: Layout) {}
+
+  /// A block described by `layout` was given back.
+  fn on_dealloc(&self, _layout: Layout) {}
+
+  /// `size` bytes of backing memory were reserved from the underlying allocator.
+  fn on_chunk_grow(&self, _size: usize) {}
+
+  /// `size` bytes of backing memory were returned to the underlying allocator.
+  fn on_chunk_release(&self, _size: usize) {}
+
+  /// All allocations were discarded at once.
+  fn on_reset(&self) {}
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Observer that ignores every event.
+pub struct NoopObserver;
+
+impl AllocObserver for NoopObserver {}
+
+impl<O> AllocObserver for &O
+where
+  O: AllocObserver + ?Sized,
+{
+  fn on_alloc(&self, layout: Layout) {
+    (**self).on_alloc(layout)
+  }
+
+  fn on_dealloc(&self, layout: Layout) {
+    (**self).on_dealloc(layout)
+  }
+
+  fn on_chunk_grow(&self, size: usize) {
+    (**self).on_chunk_grow(size)
+  }
+
+  fn on_chunk_release(&self, size: usize) {
+    (**self).on_chunk_release(size)
+  }
+
+  fn on_reset(&self) {
+    (**self).on_reset()
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+  use core::cell::Cell;
+
+  use super::*;
+
+  #[derive(Default)]
+  /// Observer that counts every event, shared by the arena and allocator tests.
+  pub(crate) struct CountingObserver {
+    pub(crate) allocs: Cell<usize>,
+    pub(crate) alloc_bytes: Cell<usize>,
+    pub(crate) deallocs: Cell<usize>,
+    pub(crate) grown: Cell<usize>,
+    pub(crate) released: Cell<usize>,
+    pub(crate) resets: Cell<usize>,
+  }
+
+  impl AllocObserver for CountingObserver {
+    fn on_alloc(&self, layout: Layout) {
+      self.allocs.set(self.allocs.get() + 1);
+      self.alloc_bytes.set(self.alloc_bytes.get() + layout.size());
+    }
+
+    fn on_dealloc(&self, _layout: Layout) {
+      self.deallocs.set(self.deallocs.get() + 1);
+    }
+
+    fn on_chunk_grow(&self, size: usize) {
+      self.grown.set(self.grown.get() + size);
+    }
+
+    fn on_chunk_release(&self, size: usize) {
+      self.released.set(self.released.get() + size);
+    }
+
+    fn on_reset(&self) {
+      self.resets.set(self.resets.get() + 1);
+    }
+  }
+}
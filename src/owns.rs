@@ -0,0 +1,18 @@
+//! Pointer-containment queries for allocators.
+
+use core::ptr::NonNull;
+
+/// Allocators that can tell whether a pointer lies in memory they manage.
+pub trait Owns {
+  /// Whether `ptr` points into memory managed by this allocator.
+  fn owns(&self, ptr: NonNull<u8>) -> bool;
+}
+
+impl<O> Owns for &O
+where
+  O: Owns + ?Sized,
+{
+  fn owns(&self, ptr: NonNull<u8>) -> bool {
+    (**self).owns(ptr)
+  }
+}
@@ -0,0 +1,63 @@
+use std::{
+  collections::BTreeMap,
+  thread,
+};
+
+use heaped::{
+  fixed::FixedAllocator,
+  global::GlobalAdapter,
+};
+
+const HEAP_SIZE: usize = 16 * 1024 * 1024;
+
+static mut HEAP: [u8; HEAP_SIZE] = [0; HEAP_SIZE];
+
+#[global_allocator]
+static GLOBAL: GlobalAdapter<FixedAllocator<'static>> =
+  GlobalAdapter::new(FixedAllocator::new(unsafe {
+    core::slice::from_raw_parts_mut((&raw mut HEAP).cast::<u8>(), HEAP_SIZE)
+  }));
+
+fn heap_contains<T: ?Sized>(value: &T) -> bool {
+  let addr = value as *const T as *const u8 as usize;
+  let start = &raw const HEAP as usize;
+  (start..start + HEAP_SIZE).contains(&addr)
+}
+
+fn main() {
+  let boxed = Box::new(0x1234_5678u64);
+  assert!(heap_contains(&*boxed));
+  assert_eq!(*boxed, 0x1234_5678);
+
+  let mut numbers = Vec::new();
+  for i in 0..10_000u32 {
+    numbers.push(i);
+  }
+  assert!(heap_contains(numbers.as_slice()));
+  assert_eq!(numbers.iter().sum::<u32>(), 49_995_000);
+
+  let aligned = Box::new(Aligned([7; 64]));
+  assert_eq!(&*aligned as *const Aligned as usize % 4096, 0);
+  assert_eq!(aligned.0[63], 7);
+
+  let handles: Vec<_> = (0..4)
+    .map(|t| {
+      thread::spawn(move || {
+        let mut map = BTreeMap::new();
+        for i in 0..1_000 {
+          map.insert(i, format!("thread {t} value {i}"));
+        }
+        map.len()
+      })
+    })
+    .collect();
+  for handle in handles {
+    assert_eq!(handle.join().unwrap(), 1_000);
+  }
+
+  let used = GLOBAL.with(|allocator| allocator.used());
+  assert!(used > 0 && used <= HEAP_SIZE);
+}
+
+#[repr(align(4096))]
+struct Aligned([u8; 64]);